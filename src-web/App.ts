@@ -1,21 +1,52 @@
-export interface ProcessStatus {
-  auCaptureOffsets: boolean;
-  auProcess: boolean;
-}
+import type { ApplyError } from './bindings/ApplyError';
+import type { Command } from './bindings/Command';
+import type { EnforceAction } from './bindings/EnforceAction';
+import type { Event } from './bindings/Event';
+import type { GameSettings } from './bindings/GameSettings';
+import type { GameSettingsField } from './bindings/GameSettingsField';
+import type { GameSettingsListItem } from './bindings/GameSettingsListItem';
+import type { GameVersion } from './bindings/GameVersion';
+import type { HistoryQuery } from './bindings/HistoryQuery';
+import type { InitResponse } from './bindings/InitResponse';
+import type { LobbyInfo } from './bindings/LobbyInfo';
+import type { MatchRecord } from './bindings/MatchRecord';
+import type { MemoryDump } from './bindings/MemoryDump';
+import type { PlayerCountRange } from './bindings/PlayerCountRange';
+import type { ProcessStatus } from './bindings/ProcessStatus';
+import type { RandomizerConstraints } from './bindings/RandomizerConstraints';
+
+// Generated from the Rust types by `cargo test`.
+export type {
+  ApplyError,
+  EnforceAction,
+  Event,
+  GameSettings,
+  GameSettingsField,
+  GameSettingsListItem,
+  GameVersion,
+  HistoryQuery,
+  LobbyInfo,
+  MatchRecord,
+  MemoryDump,
+  ProcessStatus,
+};
+export type { DriftEvent } from './bindings/DriftEvent';
+export type { GameState } from './bindings/GameState';
+export type { PlayerInfo } from './bindings/PlayerInfo';
 
 declare const window: Window & {
   external: { invoke(arg: string): void };
-  onChangeProcessStatus: ((status: ProcessStatus) => void) | null;
+  onEvent: ((event: Event) => void) | null;
 };
 
 function generateUniqueName() {
   return `_${Math.floor(((Math.random() + 1) / 2) * Number.MAX_SAFE_INTEGER)}`;
 }
 
-function invoke<T>(type: string, payload: any): Promise<T> {
+function invoke<T>({ type, payload }: Command): Promise<T> {
   return new Promise((resolve, reject) => {
     const callback = generateUniqueName();
-    (<any>window)[callback] = (err: any, value: T) => {
+    (<any>window)[callback] = (err: ApplyError | null, value: T) => {
       delete (<any>window)[callback];
       if (err != null) {
         reject(err);
@@ -33,12 +64,10 @@ function invoke<T>(type: string, payload: any): Promise<T> {
   });
 }
 
-interface GameSettingsListItem {
-  name: string;
-  gameSettings: Object | null;
-}
-
 export default class App {
+  /** The storage revision last seen. Changes made on an older one fail. */
+  private revision: string | null = null;
+
   static create() {
     if (window.external.invoke == null) {
       return new AppMock();
@@ -46,42 +75,171 @@ export default class App {
     return new App();
   }
 
-  init() {
-    return invoke<{
-      auOffsetsRepositoryUrl: string;
-      gameSettingsList: readonly GameSettingsListItem[];
-    }>('init', {});
+  async init() {
+    const response = await invoke<InitResponse>({ type: 'init', payload: {} });
+    this.revision = response.revision;
+    return response;
+  }
+
+  private async updateStorage(command: Command) {
+    this.revision = await invoke<string>(command);
   }
 
   setGameSettingsName(index: number, name: string) {
-    return invoke<void>('set_game_settings_name', { index, name });
+    return this.updateStorage({
+      type: 'set_game_settings_name',
+      payload: { index, name, revision: this.revision ?? undefined },
+    });
+  }
+
+  setGameSettingsParent(index: number, parent: string | null) {
+    return this.updateStorage({
+      type: 'set_game_settings_parent',
+      payload: {
+        index,
+        parent: parent ?? undefined,
+        revision: this.revision ?? undefined,
+      },
+    });
+  }
+
+  setPlayerCountRange(index: number, range: PlayerCountRange | null) {
+    return this.updateStorage({
+      type: 'set_player_count_range',
+      payload: {
+        index,
+        range: range ?? undefined,
+        revision: this.revision ?? undefined,
+      },
+    });
+  }
+
+  setAutoApplyRecommended(value: boolean) {
+    return this.updateStorage({
+      type: 'set_auto_apply_recommended',
+      payload: { value, revision: this.revision ?? undefined },
+    });
+  }
+
+  saveMemoryToFile(index: number, fields?: GameSettingsField[]) {
+    return this.updateStorage({
+      type: 'save_memory_to_file',
+      payload: { index, fields, revision: this.revision ?? undefined },
+    });
+  }
+
+  loadMemoryFromFile(index: number, queue = false) {
+    return invoke<void>({
+      type: 'load_memory_from_file',
+      payload: { index, queue },
+    });
+  }
+
+  cancelQueuedPreset() {
+    return invoke<void>({ type: 'cancel_queued_preset', payload: {} });
+  }
+
+  loadBuiltinPreset(builtinIndex: number) {
+    return invoke<void>({
+      type: 'load_builtin_preset',
+      payload: { builtinIndex },
+    });
+  }
+
+  copyBuiltinPreset(builtinIndex: number, index: number) {
+    return this.updateStorage({
+      type: 'copy_builtin_preset',
+      payload: { builtinIndex, index, revision: this.revision ?? undefined },
+    });
+  }
+
+  applyRandomGameSettings(constraints: RandomizerConstraints) {
+    return invoke<GameSettings>({
+      type: 'apply_random_game_settings',
+      payload: constraints,
+    });
+  }
+
+  startPlaylist(indices: number[], shuffle: boolean) {
+    return invoke<void>({
+      type: 'start_playlist',
+      payload: { indices, shuffle },
+    });
+  }
+
+  stopPlaylist() {
+    return invoke<void>({ type: 'stop_playlist', payload: {} });
+  }
+
+  startEnforce(index: number, action: EnforceAction) {
+    return invoke<void>({ type: 'start_enforce', payload: { index, action } });
+  }
+
+  stopEnforce() {
+    return invoke<void>({ type: 'stop_enforce', payload: {} });
+  }
+
+  clearEnforceLog() {
+    return invoke<void>({ type: 'clear_enforce_log', payload: {} });
+  }
+
+  history(query: HistoryQuery) {
+    return invoke<readonly MatchRecord[]>({ type: 'history', payload: query });
+  }
+
+  exportHistory(query: HistoryQuery, path: string) {
+    return invoke<number>({ type: 'export_history', payload: { query, path } });
+  }
+
+  inspectMemory() {
+    return invoke<MemoryDump>({ type: 'inspect_memory', payload: {} });
+  }
+
+  exportMemoryDump(path: string) {
+    return invoke<void>({ type: 'export_memory_dump', payload: { path } });
   }
 
-  saveMemoryToFile(index: number) {
-    return invoke<void>('save_memory_to_file', { index });
+  /** Returns where the zip was written. */
+  exportDiagnostics(path?: string) {
+    return invoke<string>({ type: 'export_diagnostics', payload: { path } });
   }
 
-  loadMemoryFromFile(index: number) {
-    return invoke<void>('load_memory_from_file', { index });
+  lobbyInfo() {
+    return invoke<LobbyInfo | null>({ type: 'lobby_info', payload: {} });
   }
 
-  setOnChangeProcessStatus(listener: ((status: ProcessStatus) => void) | null) {
-    window.onChangeProcessStatus = listener;
+  setOnEvent(listener: ((event: Event) => void) | null) {
+    window.onEvent =
+      listener &&
+      ((event) => {
+        if (event.type === 'storageChanged') {
+          this.revision = event.payload.revision;
+        }
+        listener(event);
+      });
   }
 
   openBrowser(url: string) {
-    return invoke<void>('open_browser', { url });
+    return invoke<void>({ type: 'open_browser', payload: { url } });
   }
 }
 
 export class AppMock extends App {
   async init() {
+    const gameSettings = {} as GameSettings;
     return {
       auOffsetsRepositoryUrl: 'https://google.com',
+      revision: null,
       gameSettingsList: [...Array(10).keys()].map((x) => ({
         name: `Mock ${x + 1}`,
-        gameSettings: x % 2 === 0 ? '' : null,
+        gameSettings: x % 2 === 0 ? gameSettings : null,
       })),
+      builtinGameSettingsList: [
+        { name: 'Mock default', gameSettings },
+        { name: 'Mock competitive', gameSettings },
+      ],
+      autoApplyRecommended: false,
+      debug: true,
     };
   }
 }
@@ -1,24 +1,88 @@
 import { CircularProgress } from '@material-ui/core';
 import React, { useCallback, useEffect, useMemo, useState } from 'react';
-import App from './App';
+import App, {
+  GameSettingsField,
+  GameSettingsListItem,
+  ProcessStatus,
+} from './App';
 import MainContent from './MainContent';
+import MemoryInspector from './MemoryInspector';
+import SaveDialog from './SaveDialog';
+
+function errorMessage(err: any): string {
+  return typeof err?.message === 'string'
+    ? err.message
+    : String(err?.name ?? err);
+}
 
 export default function Main() {
   const app = useMemo(() => App.create(), []);
   const [state, setState] = useState({
-    processStatus: { auCaptureOffsets: false, auProcess: false },
+    processStatus: {
+      auCaptureOffsets: false,
+      auProcess: false,
+      pid: null,
+      exePath: null,
+      dllHash: null,
+      dllHashKnown: false,
+      gameOptionsScanned: false,
+      gameVersion: null,
+      offsetsSource: '',
+      offsetsFetchedAt: null,
+      offsetsError: null,
+      captureError: null,
+      gameState: null,
+      isHost: null,
+      lobby: null,
+      queuedPreset: null,
+      recommendedPreset: null,
+      enforcement: null,
+      enforceLog: [],
+    } as ProcessStatus,
     auOffsetsRepositoryUrl: '',
-    gameSettingsList: null as
-      | readonly { name: string; gameSettings: Object | null }[]
-      | null,
+    gameSettingsList: null as readonly GameSettingsListItem[] | null,
+    builtinGameSettingsList: [] as readonly GameSettingsListItem[],
+    debug: false,
   });
+  // Bumped to remount the list so that edited fields show the stored values.
+  const [listKey, setListKey] = useState(0);
+  const onStorageError = useCallback(async (err: any) => {
+    alert(errorMessage(err));
+    const { gameSettingsList } = await app.init();
+    setState((old) => ({ ...old, gameSettingsList }));
+    setListKey((old) => old + 1);
+  }, []);
   useEffect(() => {
     (async () => {
-      app.setOnChangeProcessStatus((processStatus) => {
-        setState((old) => ({ ...old, processStatus }));
+      app.setOnEvent((event) => {
+        switch (event.type) {
+          case 'processStatusChanged':
+            setState((old) => ({ ...old, processStatus: event.payload }));
+            break;
+          case 'storageChanged':
+            setState((old) => ({
+              ...old,
+              gameSettingsList: event.payload.gameSettingsList,
+            }));
+            break;
+          case 'error':
+            alert(event.payload);
+            break;
+        }
       });
-      const { auOffsetsRepositoryUrl, gameSettingsList } = await app.init();
-      setState((old) => ({ ...old, auOffsetsRepositoryUrl, gameSettingsList }));
+      const {
+        auOffsetsRepositoryUrl,
+        gameSettingsList,
+        builtinGameSettingsList,
+        debug,
+      } = await app.init();
+      setState((old) => ({
+        ...old,
+        auOffsetsRepositoryUrl,
+        gameSettingsList,
+        builtinGameSettingsList,
+        debug,
+      }));
     })().catch(console.error);
   }, []);
 
@@ -26,34 +90,109 @@ export default function Main() {
     await app.openBrowser(state.auOffsetsRepositoryUrl);
   }, [state.auOffsetsRepositoryUrl]);
   const onChangeLabel = useCallback(async (idx, value) => {
-    await app.setGameSettingsName(idx, value);
+    try {
+      await app.setGameSettingsName(idx, value);
+    } catch (err) {
+      await onStorageError(err);
+    }
   }, []);
   const onClickLoad = useCallback(async (idx) => {
-    await app.loadMemoryFromFile(idx);
-  }, []);
-  const onClickSave = useCallback(async (idx) => {
-    await app.saveMemoryToFile(idx);
-    setState((old) => ({
-      ...old,
-      gameSettingsList:
-        old.gameSettingsList?.map((x, i) =>
-          i != idx || x.gameSettings != null ? x : { ...x, gameSettings: {} },
-        ) ?? null,
-    }));
+    try {
+      await app.loadMemoryFromFile(idx);
+    } catch (err) {
+      if (err?.name === 'NotHost') {
+        alert('Only the host can change the settings of this lobby.');
+        return;
+      }
+      if (err?.name !== 'NotInLobby') {
+        throw err;
+      }
+      if (
+        confirm('A match is in progress. Load when it returns to the lobby?')
+      ) {
+        await app.loadMemoryFromFile(idx, true);
+      }
+    }
+  }, []);
+  const [saveIndex, setSaveIndex] = useState(null as number | null);
+  const onClickSave = useCallback((idx) => {
+    setSaveIndex(idx);
+  }, []);
+  const onCloseSaveDialog = useCallback(() => {
+    setSaveIndex(null);
+  }, []);
+  const onSave = useCallback(
+    async (fields: GameSettingsField[] | undefined) => {
+      if (saveIndex == null) {
+        return;
+      }
+      setSaveIndex(null);
+      // The saved list arrives as a storageChanged event.
+      try {
+        await app.saveMemoryToFile(saveIndex, fields);
+      } catch (err) {
+        await onStorageError(err);
+      }
+    },
+    [saveIndex],
+  );
+
+  const onClickLoadBuiltin = useCallback(async (builtinIdx) => {
+    await app.loadBuiltinPreset(builtinIdx);
+  }, []);
+  const onClickCopyBuiltin = useCallback(
+    async (builtinIdx) => {
+      const idx =
+        state.gameSettingsList?.findIndex((x) => x.gameSettings == null) ?? -1;
+      if (idx < 0) {
+        return;
+      }
+      try {
+        await app.copyBuiltinPreset(builtinIdx, idx);
+      } catch (err) {
+        await onStorageError(err);
+        return;
+      }
+      const builtin = state.builtinGameSettingsList[builtinIdx];
+      setState((old) => ({
+        ...old,
+        gameSettingsList:
+          old.gameSettingsList?.map((x, i) => (i != idx ? x : builtin)) ??
+          null,
+      }));
+    },
+    [state.gameSettingsList, state.builtinGameSettingsList],
+  );
+  const onClickExportDiagnostics = useCallback(async () => {
+    const path = await app.exportDiagnostics();
+    alert(`Diagnostics saved to ${path}`);
   }, []);
 
   if (state.gameSettingsList == null) {
     return <CircularProgress />;
   }
   return (
-    <MainContent
-      processStatus={state.processStatus}
-      auOffsetsRepositoryUrl={state.auOffsetsRepositoryUrl}
-      gameSettingsList={state.gameSettingsList}
-      onClickOpenAUOffsetsRepository={onClickOpenAUOffsetsRepository}
-      onChangeLabel={onChangeLabel}
-      onClickLoad={onClickLoad}
-      onClickSave={onClickSave}
-    />
+    <>
+      <MainContent
+        key={listKey}
+        processStatus={state.processStatus}
+        auOffsetsRepositoryUrl={state.auOffsetsRepositoryUrl}
+        gameSettingsList={state.gameSettingsList}
+        builtinGameSettingsList={state.builtinGameSettingsList}
+        onClickOpenAUOffsetsRepository={onClickOpenAUOffsetsRepository}
+        onChangeLabel={onChangeLabel}
+        onClickLoad={onClickLoad}
+        onClickSave={onClickSave}
+        onClickLoadBuiltin={onClickLoadBuiltin}
+        onClickCopyBuiltin={onClickCopyBuiltin}
+        onClickExportDiagnostics={onClickExportDiagnostics}
+      />
+      <SaveDialog
+        open={saveIndex != null}
+        onClose={onCloseSaveDialog}
+        onSave={onSave}
+      />
+      {state.debug ? <MemoryInspector app={app} /> : null}
+    </>
   );
 }
@@ -7,7 +7,7 @@ import {
   CircularProgress,
 } from '@material-ui/core';
 import React, { useCallback, FocusEvent } from 'react';
-import { ProcessStatus } from './App';
+import { GameSettingsListItem, GameVersion, ProcessStatus } from './App';
 
 const useStyles = makeStyles({
   root: {
@@ -46,9 +46,14 @@ const useStyles = makeStyles({
   },
 });
 
+function formatGameVersion(gameVersion: GameVersion) {
+  return `${gameVersion.version} (${gameVersion.storefront})`;
+}
+
 function ListItem(props: {
   index: number;
   label: string;
+  gameVersion?: GameVersion;
   onChangeLabel(index: number, value: string): void;
   onClickSave(index: number): void;
   onClickLoad?: ((index: number) => void) | null;
@@ -74,6 +79,11 @@ function ListItem(props: {
       <TextField
         className={classes.text}
         defaultValue={props.label}
+        title={
+          props.gameVersion != null
+            ? `Saved on ${formatGameVersion(props.gameVersion)}`
+            : undefined
+        }
         onBlur={onChangeLabel}
       />
       <Button
@@ -97,14 +107,56 @@ function ListItem(props: {
   );
 }
 
+function BuiltinListItem(props: {
+  index: number;
+  label: string;
+  onClickCopy(index: number): void;
+  onClickLoad(index: number): void;
+}) {
+  const onClickCopy = useCallback(() => props.onClickCopy(props.index), [
+    props.onClickCopy,
+    props.index,
+  ]);
+  const onClickLoad = useCallback(() => props.onClickLoad(props.index), [
+    props.onClickLoad,
+    props.index,
+  ]);
+  const classes = useStyles();
+  return (
+    <>
+      <Typography className={classes.text}>{props.label}</Typography>
+      <Button
+        className={classes.button}
+        color="secondary"
+        variant="outlined"
+        onClick={onClickCopy}
+      >
+        Copy
+      </Button>
+      <Button
+        className={classes.button}
+        color="primary"
+        variant="outlined"
+        onClick={onClickLoad}
+      >
+        Load
+      </Button>
+    </>
+  );
+}
+
 export interface Props {
   processStatus: ProcessStatus;
   auOffsetsRepositoryUrl: string;
-  gameSettingsList: readonly { name: string; gameSettings: Object | null }[];
+  gameSettingsList: readonly GameSettingsListItem[];
+  builtinGameSettingsList: readonly GameSettingsListItem[];
   onClickOpenAUOffsetsRepository(): void;
   onChangeLabel(index: number, value: string): void;
   onClickSave(index: number): void;
   onClickLoad(index: number): void;
+  onClickLoadBuiltin(index: number): void;
+  onClickCopyBuiltin(index: number): void;
+  onClickExportDiagnostics(): void;
 }
 
 export default function MainContent(props: Props) {
@@ -134,6 +186,19 @@ export default function MainContent(props: Props) {
               Offsets repository
             </a>
           </Typography>
+          {props.processStatus.offsetsFetchedAt != null ? (
+            <Typography variant="caption">
+              Fetched at{' '}
+              {new Date(
+                props.processStatus.offsetsFetchedAt * 1000,
+              ).toLocaleString()}
+            </Typography>
+          ) : null}
+          {props.processStatus.offsetsError != null ? (
+            <Typography variant="caption">
+              ❌ {props.processStatus.offsetsError}
+            </Typography>
+          ) : null}
         </li>
         <li>
           <Typography className={classes.processStatusItem}>
@@ -145,15 +210,126 @@ export default function MainContent(props: Props) {
               )}
             </div>
             Among Us process
+            {props.processStatus.pid != null
+              ? ` (PID ${props.processStatus.pid})`
+              : ''}
+          </Typography>
+          {props.processStatus.exePath != null ? (
+            <Typography variant="caption">
+              {props.processStatus.exePath}
+            </Typography>
+          ) : null}
+          {props.processStatus.captureError != null ? (
+            <Typography variant="caption">
+              ❌ {props.processStatus.captureError}
+            </Typography>
+          ) : null}
+        </li>
+        {props.processStatus.dllHash != null ? (
+          <li>
+            <Typography className={classes.processStatusItem}>
+              <div>
+                {props.processStatus.dllHashKnown ||
+                props.processStatus.gameOptionsScanned
+                  ? '✅'
+                  : '⚠️'}
+              </div>
+              {props.processStatus.dllHashKnown
+                ? 'Supported game version'
+                : props.processStatus.gameOptionsScanned
+                ? 'Game settings located by signature scan'
+                : 'Unsupported game version'}
+              {props.processStatus.gameVersion != null
+                ? `: ${formatGameVersion(props.processStatus.gameVersion)}`
+                : ''}
+            </Typography>
+            <Typography variant="caption" title="GameAssembly.dll SHA-256">
+              {props.processStatus.dllHash}
+            </Typography>
+          </li>
+        ) : null}
+        <li>
+          <Typography className={classes.processStatusItem}>
+            <div>
+              {props.processStatus.gameState != null ? (
+                '✅'
+              ) : (
+                <CircularProgress size="2ex" />
+              )}
+            </div>
+            Game state
+            {props.processStatus.gameState != null
+              ? `: ${props.processStatus.gameState}`
+              : ''}
           </Typography>
         </li>
+        {props.processStatus.lobby != null ? (
+          <li>
+            <Typography className={classes.processStatusItem}>
+              <div>👥</div>
+              {props.processStatus.lobby.gameCode ?? '------'} (
+              {props.processStatus.lobby.playerCount} players)
+            </Typography>
+            <Typography variant="caption">
+              {props.processStatus.lobby.players.map((x) => x.name).join(', ')}
+            </Typography>
+          </li>
+        ) : null}
+        {props.processStatus.isHost === false ? (
+          <li>
+            <Typography className={classes.processStatusItem}>
+              <div>⚠️</div>
+              Not the host of this lobby
+            </Typography>
+          </li>
+        ) : null}
+        {props.processStatus.recommendedPreset != null ? (
+          <li>
+            <Typography className={classes.processStatusItem}>
+              <div>💡</div>
+              Recommended:{' '}
+              {
+                props.gameSettingsList[props.processStatus.recommendedPreset]
+                  ?.name
+              }
+            </Typography>
+          </li>
+        ) : null}
+        {props.processStatus.enforcement != null ? (
+          <li>
+            <Typography className={classes.processStatusItem}>
+              <div>🔒</div>
+              Enforcing:{' '}
+              {
+                props.gameSettingsList[props.processStatus.enforcement.preset]
+                  ?.name
+              }
+            </Typography>
+            {props.processStatus.enforceLog.slice(-5).map((x, i) => (
+              <Typography key={i} variant="caption" component="div">
+                {new Date(x.timestamp * 1000).toLocaleTimeString()} {x.field}:{' '}
+                {x.actual} → {x.expected} ({x.action})
+              </Typography>
+            ))}
+          </li>
+        ) : null}
+        {props.processStatus.queuedPreset != null ? (
+          <li>
+            <Typography className={classes.processStatusItem}>
+              <div>⏳</div>
+              Queued:{' '}
+              {props.gameSettingsList[props.processStatus.queuedPreset]?.name}
+            </Typography>
+          </li>
+        ) : null}
       </ul>
       <ul className={classes.listContainer}>
         {props.gameSettingsList.map((x, i) => (
-          <li className={classes.listItem}>
+          <li key={`${i}-${x.name}`} className={classes.listItem}>
             <ListItem
               index={i}
               label={x.name}
+              gameVersion={x.gameVersion}
               onChangeLabel={props.onChangeLabel}
               onClickSave={props.onClickSave}
               onClickLoad={x.gameSettings == null ? null : props.onClickLoad}
@@ -161,6 +337,22 @@ export default function MainContent(props: Props) {
           </li>
         ))}
       </ul>
+      <Typography variant="subtitle2">Built-in presets</Typography>
+      <ul className={classes.listContainer}>
+        {props.builtinGameSettingsList.map((x, i) => (
+          <li key={i} className={classes.listItem}>
+            <BuiltinListItem
+              index={i}
+              label={x.name}
+              onClickCopy={props.onClickCopyBuiltin}
+              onClickLoad={props.onClickLoadBuiltin}
+            />
+          </li>
+        ))}
+      </ul>
+      <Button size="small" onClick={props.onClickExportDiagnostics}>
+        Export diagnostics
+      </Button>
     </Container>
   );
 }
@@ -0,0 +1,95 @@
+import {
+  Button,
+  Container,
+  TextField,
+  Typography,
+  makeStyles,
+} from '@material-ui/core';
+import React, { useCallback, useState } from 'react';
+import App, { MemoryDump } from './App';
+
+const useStyles = makeStyles({
+  root: {
+    marginBottom: '16px',
+  },
+  table: {
+    fontFamily: 'monospace',
+    fontSize: '12px',
+    borderCollapse: 'collapse',
+    '& td': {
+      padding: '0 6px',
+    },
+  },
+  changed: {
+    color: 'red',
+    fontWeight: 'bold',
+  },
+});
+
+function hex(value: number, width: number) {
+  return value.toString(16).toUpperCase().padStart(width, '0');
+}
+
+/** Dumps the GameSettings region for finding offsets after game updates. */
+export default function MemoryInspector(props: { app: App }) {
+  const classes = useStyles();
+  const [dump, setDump] = useState(null as MemoryDump | null);
+  const [path, setPath] = useState('');
+  const onClickInspect = useCallback(async () => {
+    setDump(await props.app.inspectMemory());
+  }, [props.app]);
+  const onClickExport = useCallback(async () => {
+    await props.app.exportMemoryDump(path);
+  }, [props.app, path]);
+  return (
+    <Container className={classes.root}>
+      <Typography variant="subtitle2">Memory inspector</Typography>
+      <Button size="small" variant="outlined" onClick={onClickInspect}>
+        Inspect
+      </Button>
+      {dump != null ? (
+        <>
+          <Typography variant="caption">
+            GameSettings: 0x{hex(dump.baseAddr, 8)}
+          </Typography>
+          <table className={classes.table}>
+            <tbody>
+              {dump.rows.map((row) => (
+                <tr key={row.offset}>
+                  <td>+{hex(row.offset, 2)}</td>
+                  <td>
+                    {row.bytes.map((x, i) => (
+                      <span
+                        key={i}
+                        className={row.changed[i] ? classes.changed : undefined}
+                      >
+                        {hex(x, 2)}{' '}
+                      </span>
+                    ))}
+                  </td>
+                  <td>{row.asI32}</td>
+                  <td>{row.asF32 ?? 'NaN'}</td>
+                  <td>{row.asBool ? 'true' : 'false'}</td>
+                  <td>{row.fields.join(', ')}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+          <TextField
+            label="Export path"
+            value={path}
+            onChange={(e) => setPath(e.target.value)}
+          />
+          <Button
+            size="small"
+            variant="outlined"
+            disabled={path === ''}
+            onClick={onClickExport}
+          >
+            Export
+          </Button>
+        </>
+      ) : null}
+    </Container>
+  );
+}
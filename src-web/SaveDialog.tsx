@@ -0,0 +1,91 @@
+import {
+  Button,
+  Checkbox,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  FormControlLabel,
+  FormGroup,
+} from '@material-ui/core';
+import React, { useCallback, useEffect, useState } from 'react';
+import { GameSettingsField } from './App';
+
+const FIELD_LABELS: { readonly [field in GameSettingsField]: string } = {
+  map: 'Map',
+  playerSpeed: 'Player speed',
+  crewmateVision: 'Crewmate vision',
+  impostorVision: 'Impostor vision',
+  killCooldown: 'Kill cooldown',
+  commonTasks: 'Common tasks',
+  longTasks: 'Long tasks',
+  shortTasks: 'Short tasks',
+  emergencyMeeting: 'Emergency meetings',
+  emergencyCooldown: 'Emergency cooldown',
+  impostors: 'Impostors',
+  killDistance: 'Kill distance',
+  discussionTime: 'Discussion time',
+  votingTime: 'Voting time',
+  confirmEject: 'Confirm ejects',
+  visualTasks: 'Visual tasks',
+  anonymousVoting: 'Anonymous votes',
+  taskBarUpdates: 'Task bar updates',
+};
+
+const ALL_FIELDS = Object.keys(FIELD_LABELS) as readonly GameSettingsField[];
+
+/** Chooses which fields of the running game a preset saves. */
+export default function SaveDialog(props: {
+  open: boolean;
+  onClose(): void;
+  /** `undefined` means every field. */
+  onSave(fields: GameSettingsField[] | undefined): void;
+}) {
+  const [fields, setFields] = useState(ALL_FIELDS);
+  useEffect(() => {
+    if (props.open) {
+      setFields(ALL_FIELDS);
+    }
+  }, [props.open]);
+  const onChange = useCallback((field: GameSettingsField, checked: boolean) => {
+    setFields((old) =>
+      checked
+        ? ALL_FIELDS.filter((x) => x === field || old.includes(x))
+        : old.filter((x) => x !== field),
+    );
+  }, []);
+  const onClickSave = useCallback(() => {
+    props.onSave(fields.length === ALL_FIELDS.length ? undefined : [...fields]);
+  }, [props.onSave, fields]);
+  return (
+    <Dialog open={props.open} onClose={props.onClose}>
+      <DialogTitle>Fields to save</DialogTitle>
+      <DialogContent>
+        <FormGroup>
+          {ALL_FIELDS.map((field) => (
+            <FormControlLabel
+              key={field}
+              label={FIELD_LABELS[field]}
+              control={
+                <Checkbox
+                  checked={fields.includes(field)}
+                  onChange={(e) => onChange(field, e.target.checked)}
+                />
+              }
+            />
+          ))}
+        </FormGroup>
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={props.onClose}>Cancel</Button>
+        <Button
+          color="secondary"
+          disabled={fields.length === 0}
+          onClick={onClickSave}
+        >
+          Save
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
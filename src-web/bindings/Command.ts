@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EnforceAction } from "./EnforceAction";
+import type { GameSettingsField } from "./GameSettingsField";
+import type { HistoryQuery } from "./HistoryQuery";
+import type { PlayerCountRange } from "./PlayerCountRange";
+import type { RandomizerConstraints } from "./RandomizerConstraints";
+
+/**
+ * A request from the UI or another instance, e.g.
+ * `{"type":"load_memory_from_file","payload":{"index":0}}`.
+ */
+export type Command = { "type": "init", "payload": Record<string, never> } | { "type": "open_browser", "payload": { url: string, } } | { "type": "set_game_settings_name", "payload": { index: number, name: string, revision?: string, } } | { "type": "set_game_settings_parent", "payload": { index: number, parent?: string, revision?: string, } } | { "type": "set_player_count_range", "payload": { index: number, range?: PlayerCountRange, revision?: string, } } | { "type": "set_auto_apply_recommended", "payload": { value: boolean, revision?: string, } } | { "type": "save_memory_to_file", "payload": { index: number, fields?: Array<GameSettingsField>, revision?: string, } } | { "type": "load_memory_from_file", "payload": { index: number, queue: boolean, } } | { "type": "cancel_queued_preset", "payload": Record<string, never> } | { "type": "load_builtin_preset", "payload": { builtinIndex: number, } } | { "type": "copy_builtin_preset", "payload": { builtinIndex: number, index: number, revision?: string, } } | { "type": "apply_random_game_settings", "payload": RandomizerConstraints } | { "type": "start_playlist", "payload": { indices: Array<number>, shuffle: boolean, } } | { "type": "stop_playlist", "payload": Record<string, never> } | { "type": "start_enforce", "payload": { index: number, action: EnforceAction, } } | { "type": "stop_enforce", "payload": Record<string, never> } | { "type": "clear_enforce_log", "payload": Record<string, never> } | { "type": "history", "payload": HistoryQuery } | { "type": "export_history", "payload": { query: HistoryQuery, path: string, } } | { "type": "lobby_info", "payload": Record<string, never> } | { "type": "inspect_memory", "payload": Record<string, never> } | { "type": "export_memory_dump", "payload": { path: string, } } | { "type": "export_diagnostics", "payload": { path?: string, } } | { "type": "watch", "payload": Record<string, never> };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EnforceAction } from "./EnforceAction";
+import type { GameSettingsField } from "./GameSettingsField";
+
+export type DriftEvent = { 
+/**
+ * Seconds since the Unix epoch.
+ */
+timestamp: number, field: GameSettingsField, expected: number, actual: number, action: EnforceAction, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameInfo } from "./GameInfo";
+import type { GameSettingsField } from "./GameSettingsField";
+import type { GameSettingsListItem } from "./GameSettingsListItem";
+import type { ProcessStatus } from "./ProcessStatus";
+
+/**
+ * Pushed from core to every subscriber: the web UI and `--watch` clients.
+ */
+export type Event = { "type": "processAttached" } | { "type": "processDetached" } | { "type": "offsetsLoaded" } | { "type": "offsetsFailed", "payload": string } | { "type": "gameStateChanged", "payload": GameInfo } | { "type": "settingsApplied", "payload": Array<GameSettingsField> } | { "type": "settingsDrifted", "payload": Array<GameSettingsField> } | { "type": "storageChanged", "payload": { gameSettingsList: Array<GameSettingsListItem>, revision: string | null, } } | { "type": "appStateChanged" } | { "type": "processStatusChanged", "payload": ProcessStatus } | { "type": "error", "payload": string };
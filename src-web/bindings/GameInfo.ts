@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameState } from "./GameState";
+import type { LobbyInfo } from "./LobbyInfo";
+
+/**
+ * Everything the game state watcher reads from the process on each tick.
+ */
+export type GameInfo = { gameState: GameState | null, isHost: boolean | null, lobby: LobbyInfo | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type GameSettings = { playerSpeed: number, crewmateVision: number, impostorVision: number, killCooldown: number, commonTasks: number, longTasks: number, shortTasks: number, emergencyMeeting: number, emergencyCooldown: number, killDistance: number, discussionTime: number, votingTime: number, confirmEject: boolean, visualTasks: boolean, anonymousVoting: boolean, taskBarUpdates: number, };
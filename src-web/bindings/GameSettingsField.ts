@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type GameSettingsField = "map" | "playerSpeed" | "crewmateVision" | "impostorVision" | "killCooldown" | "commonTasks" | "longTasks" | "shortTasks" | "emergencyMeeting" | "emergencyCooldown" | "impostors" | "killDistance" | "discussionTime" | "votingTime" | "confirmEject" | "visualTasks" | "anonymousVoting" | "taskBarUpdates";
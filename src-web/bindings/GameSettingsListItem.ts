@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameSettings } from "./GameSettings";
+import type { GameSettingsField } from "./GameSettingsField";
+import type { GameVersion } from "./GameVersion";
+import type { PlayerCountRange } from "./PlayerCountRange";
+
+export type GameSettingsListItem = { name: string, gameSettings: GameSettings | null, 
+/**
+ * Fields applied on load. `None` means every field.
+ */
+fields?: Array<GameSettingsField>, 
+/**
+ * Name of the preset whose resolved settings this one overrides.
+ */
+parent?: string, 
+/**
+ * Lobby sizes this preset is meant for.
+ */
+playerCount?: PlayerCountRange, 
+/**
+ * Game version the settings were captured on.
+ */
+gameVersion?: GameVersion, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Storefront } from "./Storefront";
+
+export type GameVersion = { 
+/**
+ * e.g. `2021.3.5`
+ */
+version: string, storefront: Storefront, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameSettingsListItem } from "./GameSettingsListItem";
+
+export type InitResponse = { auOffsetsRepositoryUrl: string, 
+/**
+ * Revision of the storage to pass back with changes.
+ */
+revision: string | null, gameSettingsList: Array<GameSettingsListItem>, builtinGameSettingsList: Array<GameSettingsListItem>, autoApplyRecommended: boolean, 
+/**
+ * Enables developer tools such as the memory inspector.
+ */
+debug: boolean, };
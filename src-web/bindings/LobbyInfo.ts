@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlayerInfo } from "./PlayerInfo";
+
+export type LobbyInfo = { gameCode: string | null, playerCount: number, players: Array<PlayerInfo>, };
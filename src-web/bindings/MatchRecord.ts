@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameSettings } from "./GameSettings";
+
+export type MatchRecord = { 
+/**
+ * Seconds since the Unix epoch.
+ */
+timestamp: number, gameCode: string | null, playerCount: number | null, 
+/**
+ * `GameSettings` does not serialize these two, so they are kept here.
+ */
+map: number, impostors: number, gameSettings: GameSettings, };
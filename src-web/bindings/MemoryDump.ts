@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LayoutEntry } from "./LayoutEntry";
+import type { MemoryRow } from "./MemoryRow";
+
+export type MemoryDump = { baseAddr: number, layout: Array<LayoutEntry>, rows: Array<MemoryRow>, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameSettingsField } from "./GameSettingsField";
+
+/**
+ * One 4-byte word of the region, read as every type a field may have.
+ */
+export type MemoryRow = { offset: number, bytes: Array<number>, asI32: number, 
+/**
+ * `null` for NaN and infinities.
+ */
+asF32: number | null, asBool: boolean, 
+/**
+ * Per byte, whether it differs from the previous snapshot.
+ */
+changed: Array<boolean>, 
+/**
+ * Known fields that start within this word.
+ */
+fields: Array<GameSettingsField>, };
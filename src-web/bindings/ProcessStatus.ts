@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DriftEvent } from "./DriftEvent";
+import type { Enforcement } from "./Enforcement";
+import type { GameState } from "./GameState";
+import type { GameVersion } from "./GameVersion";
+import type { LobbyInfo } from "./LobbyInfo";
+
+export type ProcessStatus = { auCaptureOffsets: boolean, auProcess: boolean, pid: number | null, exePath: string | null, dllHash: string | null, 
+/**
+ * Whether the loaded offsets cover `dll_hash`. False means this game
+ * version is not supported yet.
+ */
+dllHashKnown: boolean, 
+/**
+ * Whether `GameOptionsOffset` comes from signature scanning because the
+ * offsets do not cover `dll_hash`.
+ */
+gameOptionsScanned: boolean, gameVersion: GameVersion | null, offsetsSource: string, offsetsFetchedAt: number | null, 
+/**
+ * Why the last offsets fetch failed.
+ */
+offsetsError: string | null, 
+/**
+ * Why the last process capture failed.
+ */
+captureError: string | null, queuedPreset: number | null, recommendedPreset: number | null, enforcement: Enforcement | null, enforceLog: Array<DriftEvent>, gameState: GameState | null, isHost: boolean | null, lobby: LobbyInfo | null, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Bounds } from "./Bounds";
+import type { GameSettingsField } from "./GameSettingsField";
+
+export type RandomizerConstraints = { bounds?: { [key in GameSettingsField]?: Bounds }, maxTotalTasks?: number, seed?: number, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApplyError } from "./ApplyError";
+import type { JsonValue } from "./serde_json/JsonValue";
+
+export type Response = { "ok": JsonValue } | { "err": ApplyError };
@@ -2,10 +2,27 @@ pub mod app;
 mod aucaptureoffsets;
 mod auprocess;
 mod auprocessreadwrite;
+mod clock;
+pub mod command;
+mod diagnostics;
+mod enforce;
+pub mod event;
 mod game_settings;
+mod game_state;
+mod game_version;
+mod history;
+mod inspector;
+pub mod instance;
+mod lobby;
+pub mod logging;
+pub mod paths;
+mod pe;
+mod playlist;
 mod process;
 #[cfg(windows)]
 mod process_impl;
 #[cfg(not(windows))]
 mod process_mock;
+mod randomizer;
+mod signature;
 mod storage;
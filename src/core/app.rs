@@ -1,40 +1,131 @@
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
+use log::{debug, error, info, warn};
 use serde::Serialize;
 use tokio::{
     spawn,
     sync::{
-        mpsc::{self, Sender},
+        broadcast::{self, error::RecvError, Receiver, Sender},
         RwLock,
     },
-    task::JoinHandle,
+    task::{spawn_blocking, JoinHandle},
     time::interval,
 };
+#[cfg(test)]
+use ts_rs::TS;
 
 use super::{
     aucaptureoffsets::{AUCaptureOffsets, AUCaptureOffsetsError},
     auprocess::{AUProcess, AUProcessError},
     auprocessreadwrite::AUProcessReadWrite,
-    storage::{GameSettingsListItem, Storage},
+    clock::unix_time,
+    diagnostics::{self, Bundle},
+    enforce::{drifted_fields, record_drift, DriftEvent, EnforceAction, Enforcement},
+    event::{emit, Event},
+    game_settings::{GameSettings, GameSettingsField},
+    game_state::{GameInfo, GameState},
+    game_version::{self, GameVersion},
+    history::{self, HistoryQuery, MatchRecord},
+    inspector::{self, MemoryDump, Snapshot, REGION_SIZE},
+    lobby::LobbyInfo,
+    playlist::Playlist,
+    randomizer::{self, RandomizerConstraints, RandomizerError},
+    signature,
+    storage::{builtin_presets, GameSettingsListItem, PlayerCountRange, Storage, StorageError},
 };
 
 #[derive(Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
 #[serde(rename_all = "camelCase")]
 pub struct InitResponse<'a> {
     pub au_offsets_repository_url: &'a str,
+    /// Revision of the storage to pass back with changes.
+    pub revision: Option<String>,
     pub game_settings_list: Vec<GameSettingsListItem>,
+    pub builtin_game_settings_list: Vec<GameSettingsListItem>,
+    pub auto_apply_recommended: bool,
+    /// Enables developer tools such as the memory inspector.
+    pub debug: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
 #[serde(rename_all = "camelCase")]
 pub struct ProcessStatus {
     pub au_capture_offsets: bool,
     pub au_process: bool,
+    pub pid: Option<u32>,
+    pub exe_path: Option<String>,
+    pub dll_hash: Option<String>,
+    /// Whether the loaded offsets cover `dll_hash`. False means this game
+    /// version is not supported yet.
+    pub dll_hash_known: bool,
+    /// Whether `GameOptionsOffset` comes from signature scanning because the
+    /// offsets do not cover `dll_hash`.
+    pub game_options_scanned: bool,
+    pub game_version: Option<GameVersion>,
+    pub offsets_source: String,
+    #[cfg_attr(test, ts(type = "number | null"))]
+    pub offsets_fetched_at: Option<u64>,
+    /// Why the last offsets fetch failed.
+    pub offsets_error: Option<String>,
+    /// Why the last process capture failed.
+    pub capture_error: Option<String>,
+    #[serde(flatten)]
+    pub game_info: GameInfo,
+    pub queued_preset: Option<usize>,
+    pub recommended_preset: Option<usize>,
+    pub enforcement: Option<Enforcement>,
+    pub enforce_log: Vec<DriftEvent>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(tag = "name", content = "message")]
+pub enum ApplyError {
+    Error(String),
+    InvalidCommand(String),
+    NoSuchPreset(usize),
+    NotInLobby,
+    NotHost,
+}
+
+impl From<StorageError> for ApplyError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::NoSuchPreset(idx) => ApplyError::NoSuchPreset(idx),
+            err => ApplyError::Error(storage_error_message(&err)),
+        }
+    }
+}
+
+fn storage_error_message(err: &StorageError) -> String {
+    match err {
+        StorageError::NoSuchPreset(idx) => format!("No such preset ({})", idx),
+        StorageError::NoData(name) => format!("No data ({})", name),
+        StorageError::MissingParent(name) => format!("Parent not found ({})", name),
+        StorageError::Cycle(names) => format!("Parent cycle ({})", names.join(" -> ")),
+        StorageError::NameTaken(name) => format!("Name already in use ({})", name),
+        StorageError::Io(message) => format!("File access failed ({})", message),
+        StorageError::Malformed(message) => format!("ausettings.json is malformed ({})", message),
+        StorageError::Stale => "ausettings.json was changed by another program".into(),
+    }
+}
+
+/// Details about the last offsets fetch and process capture.
+#[derive(Default)]
+struct Diagnostics {
+    /// Whether the offsets fetch has either succeeded or failed.
+    offsets_fetch_finished: bool,
+    offsets_fetched_at: Option<u64>,
+    offsets_error: Option<String>,
+    capture_error: Option<String>,
 }
 
 async fn fetch_offsets(
     au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
-    on_change_status: Sender<()>,
+    diagnostics_lock: &RwLock<Diagnostics>,
+    events: &Sender<Event>,
     url: &str,
 ) {
     match AUCaptureOffsets::fetch(url) {
@@ -43,22 +134,92 @@ async fn fetch_offsets(
                 AUCaptureOffsetsError::FetchFailed(err) => format!("Fetch failed ({})", err),
                 AUCaptureOffsetsError::ParseFailed(err) => format!("Parse failed ({})", err),
             };
-            eprintln!("Fetch failed: {}", msg);
+            error!("Fetch failed: {}", msg);
+            let mut diagnostics = diagnostics_lock.write().await;
+            diagnostics.offsets_fetch_finished = true;
+            diagnostics.offsets_error = Some(msg.clone());
+            drop(diagnostics);
+            emit(events, Event::OffsetsFailed(msg));
         }
         Ok(au_capture_offsets) => {
             *(au_capture_offsets_lock.write().await) = Some(au_capture_offsets);
-            on_change_status.send(()).await.unwrap();
+            let mut diagnostics = diagnostics_lock.write().await;
+            diagnostics.offsets_fetch_finished = true;
+            diagnostics.offsets_fetched_at = Some(unix_time());
+            diagnostics.offsets_error = None;
+            drop(diagnostics);
+            emit(events, Event::OffsetsLoaded);
         }
     }
 }
 
+/// Looks for `GameOptionsOffset` by signature once the offsets fetch has
+/// finished without covering the captured GameAssembly.dll. Runs once per
+/// process, on a blocking thread since it reads the whole DLL.
+async fn scan_game_options_offset(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    diagnostics_lock: &RwLock<Diagnostics>,
+    events: &Sender<Event>,
+) {
+    if !diagnostics_lock.read().await.offsets_fetch_finished {
+        return;
+    }
+    let (dll_path, process) = {
+        let au_process_guard = au_process_lock.read().await;
+        let au_process = match au_process_guard.as_ref() {
+            Some(x) if !x.scanned() => x,
+            _ => return,
+        };
+        let covered = au_capture_offsets_lock
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|x| x.game_options_offset(au_process.dll_hash()).is_some());
+        if covered {
+            return;
+        }
+        (
+            au_process.dll_path().to_owned(),
+            au_process.shared_process(),
+        )
+    };
+    info!("Scanning {} for signatures...", dll_path);
+    let scanned_process = process.clone();
+    let offset = spawn_blocking(move || signature::game_options_offset(&dll_path, &process))
+        .await
+        .unwrap_or_else(|err| {
+            error!("Signature scan failed. {}", err);
+            None
+        });
+    // The game may have restarted while scanning.
+    match au_process_lock.write().await.as_mut() {
+        Some(au_process) if Arc::ptr_eq(&au_process.shared_process(), &scanned_process) => {
+            au_process.set_scanned_game_options_offset(offset)
+        }
+        _ => return,
+    }
+    if offset.is_some() {
+        emit(events, Event::OffsetsLoaded);
+    }
+}
+
 async fn capture_process(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
     au_process_lock: &RwLock<Option<AUProcess>>,
-    on_change_status: Sender<()>,
+    diagnostics_lock: &RwLock<Diagnostics>,
+    events: &Sender<Event>,
 ) {
     let mut interval = interval(Duration::from_secs(3));
     loop {
         interval.tick().await;
+        scan_game_options_offset(
+            au_capture_offsets_lock,
+            au_process_lock,
+            diagnostics_lock,
+            events,
+        )
+        .await;
         let process_dead = if let Some(au_process) = au_process_lock.read().await.as_ref() {
             if au_process.process().is_active() {
                 continue;
@@ -69,143 +230,959 @@ async fn capture_process(
         };
         if process_dead {
             *(au_process_lock.write().await) = None;
+            emit(events, Event::ProcessDetached);
         }
-        println!("Capturing au process...");
+        debug!("Capturing au process...");
         match AUProcess::new() {
             Err(err) => {
                 let msg = match err {
                     AUProcessError::ProcessNotFound => "Process not found".into(),
                     AUProcessError::DllNotFound(err) => format!("DLL not found({})", err),
                 };
-                eprintln!("Capture failed: {}", msg);
-                on_change_status.send(()).await.unwrap();
+                let mut diagnostics = diagnostics_lock.write().await;
+                if diagnostics.capture_error.as_ref() == Some(&msg) {
+                    debug!("Capture failed: {}", msg);
+                } else {
+                    warn!("Capture failed: {}", msg);
+                    diagnostics.capture_error = Some(msg);
+                    drop(diagnostics);
+                    emit(events, Event::AppStateChanged);
+                }
             }
             Ok(au_process) => {
-                println!("Captured.");
+                info!(
+                    "Captured {} ({}).",
+                    au_process.exe_path(),
+                    au_process.dll_hash()
+                );
+                diagnostics_lock.write().await.capture_error = None;
                 *(au_process_lock.write().await) = Some(au_process);
-                on_change_status.send(()).await.unwrap();
+                emit(events, Event::ProcessAttached);
+            }
+        }
+    }
+}
+
+async fn apply_game_settings(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    events: &Sender<Event>,
+    game_settings: GameSettings,
+    fields: &[GameSettingsField],
+) -> Result<(), ApplyError> {
+    let unavailable = || ApplyError::Error("Among Us process is not available".into());
+    let au_capture_offsets_guard = au_capture_offsets_lock.read().await;
+    let au_process_guard = au_process_lock.read().await;
+    let au_process = au_process_guard.as_ref().ok_or_else(unavailable)?;
+    AUProcessReadWrite::new(au_capture_offsets_guard.as_ref(), au_process)
+        .ok_or_else(unavailable)?
+        .set_game_settings(game_settings, fields)
+        .ok_or_else(unavailable)?;
+    emit(events, Event::SettingsApplied(fields.to_vec()));
+    Ok(())
+}
+
+async fn load_preset(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    events: &Sender<Event>,
+    idx: usize,
+) -> Result<(), ApplyError> {
+    let (game_settings, fields) = Storage::load()?.resolve(idx)?;
+    apply_game_settings(
+        au_capture_offsets_lock,
+        au_process_lock,
+        events,
+        game_settings,
+        &fields,
+    )
+    .await
+}
+
+async fn read_game_info(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+) -> GameInfo {
+    let au_capture_offsets_guard = au_capture_offsets_lock.read().await;
+    let au_process_guard = au_process_lock.read().await;
+    let au_process_read_write = au_process_guard
+        .as_ref()
+        .and_then(|x| AUProcessReadWrite::new(au_capture_offsets_guard.as_ref(), x));
+    let au_process_read_write = match au_process_read_write {
+        None => return GameInfo::default(),
+        Some(x) => x,
+    };
+    let game_state = au_process_read_write.game_state();
+    let in_lobby_or_match = game_state.is_some_and(|x| x != GameState::Menu);
+    GameInfo {
+        game_state,
+        is_host: au_process_read_write.is_host(),
+        lobby: if in_lobby_or_match {
+            au_process_read_write.lobby_info()
+        } else {
+            None
+        },
+    }
+}
+
+fn check_applicable(
+    game_state: Option<GameState>,
+    is_host: Option<bool>,
+) -> Result<(), ApplyError> {
+    match game_state {
+        None | Some(GameState::Menu) => Ok(()),
+        Some(_) if is_host == Some(false) => Err(ApplyError::NotHost),
+        Some(x) if x.is_playing() => Err(ApplyError::NotInLobby),
+        Some(_) => Ok(()),
+    }
+}
+
+async fn advance_playlist(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    events: &Sender<Event>,
+    playlist_lock: &RwLock<Option<Playlist>>,
+) {
+    let idx = match playlist_lock.write().await.as_mut() {
+        None => return,
+        Some(playlist) => match playlist.next() {
+            None => return,
+            Some(x) => x,
+        },
+    };
+    info!("Playlist: loading {}", idx);
+    if let Err(err) = load_preset(au_capture_offsets_lock, au_process_lock, events, idx).await {
+        error!("Playlist: load failed. {:?}", err);
+        emit(
+            events,
+            Event::Error(format!("Playlist: load failed. {:?}", err)),
+        );
+    }
+}
+
+async fn load_queued_preset(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    events: &Sender<Event>,
+    queued_preset_lock: &RwLock<Option<usize>>,
+) {
+    let idx = match queued_preset_lock.write().await.take() {
+        None => return,
+        Some(x) => x,
+    };
+    info!("Queue: loading {}", idx);
+    if let Err(err) = load_preset(au_capture_offsets_lock, au_process_lock, events, idx).await {
+        error!("Queue: load failed. {:?}", err);
+        emit(
+            events,
+            Event::Error(format!("Queue: load failed. {:?}", err)),
+        );
+    }
+}
+
+async fn recommend_preset(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    events: &Sender<Event>,
+    recommended_preset_lock: &RwLock<Option<usize>>,
+    player_count: Option<usize>,
+    applicable: bool,
+) {
+    // A malformed file is reported by the storage watcher.
+    let storage = match Storage::load() {
+        Err(_) => return,
+        Ok(x) => x,
+    };
+    let recommended_preset = player_count.and_then(|x| storage.recommend(x));
+    let last_recommended_preset = std::mem::replace(
+        &mut *recommended_preset_lock.write().await,
+        recommended_preset,
+    );
+    let idx = match recommended_preset {
+        Some(x) if Some(x) != last_recommended_preset => x,
+        _ => return,
+    };
+    info!("Recommended: {}", idx);
+    if !storage.auto_apply_recommended || !applicable {
+        return;
+    }
+    if let Err(err) = load_preset(au_capture_offsets_lock, au_process_lock, events, idx).await {
+        error!("Recommended: load failed. {:?}", err);
+        emit(
+            events,
+            Event::Error(format!("Recommended: load failed. {:?}", err)),
+        );
+    }
+}
+
+async fn record_match(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    events: &Sender<Event>,
+    lobby: Option<&LobbyInfo>,
+) {
+    let game_settings = {
+        let au_capture_offsets_guard = au_capture_offsets_lock.read().await;
+        let au_process_guard = au_process_lock.read().await;
+        au_process_guard
+            .as_ref()
+            .and_then(|x| AUProcessReadWrite::new(au_capture_offsets_guard.as_ref(), x))
+            .and_then(|x| x.game_settings())
+    };
+    let game_settings = match game_settings {
+        None => return,
+        Some(x) => x,
+    };
+    let record = MatchRecord {
+        timestamp: unix_time(),
+        game_code: lobby.and_then(|x| x.game_code.clone()),
+        player_count: lobby.map(|x| x.player_count),
+        map: game_settings.map,
+        impostors: game_settings.impostors,
+        game_settings,
+    };
+    if let Err(err) = history::append(&record) {
+        error!("History: file output failed. {}", err);
+        emit(
+            events,
+            Event::Error(format!("History: file output failed. {}", err)),
+        );
+    }
+}
+
+async fn watch_game_state(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    game_info_lock: &RwLock<GameInfo>,
+    playlist_lock: &RwLock<Option<Playlist>>,
+    queued_preset_lock: &RwLock<Option<usize>>,
+    recommended_preset_lock: &RwLock<Option<usize>>,
+    events: &Sender<Event>,
+) {
+    let mut interval = interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        let game_info = read_game_info(au_capture_offsets_lock, au_process_lock).await;
+        let last_game_info = {
+            let mut game_info_guard = game_info_lock.write().await;
+            if *game_info_guard == game_info {
+                continue;
             }
+            std::mem::replace(&mut *game_info_guard, game_info.clone())
+        };
+        emit(events, Event::GameStateChanged(game_info.clone()));
+        let GameInfo {
+            game_state,
+            is_host,
+            lobby,
+        } = game_info;
+        let last_game_state = last_game_info.game_state;
+        let in_applicable_lobby =
+            game_state == Some(GameState::Lobby) && check_applicable(game_state, is_host).is_ok();
+        if last_game_state != game_state || last_game_info.is_host != is_host {
+            info!(
+                "Game state: {:?} -> {:?} (host: {:?})",
+                last_game_state, game_state, is_host
+            );
+        }
+        let match_started = game_state.is_some_and(|x| x.is_playing())
+            && !last_game_state.is_some_and(|x| x.is_playing());
+        if match_started {
+            record_match(
+                au_capture_offsets_lock,
+                au_process_lock,
+                events,
+                lobby.as_ref().or(last_game_info.lobby.as_ref()),
+            )
+            .await;
+        }
+        let player_count = lobby.map(|x| x.player_count);
+        if player_count != last_game_info.lobby.map(|x| x.player_count) {
+            recommend_preset(
+                au_capture_offsets_lock,
+                au_process_lock,
+                events,
+                recommended_preset_lock,
+                player_count,
+                in_applicable_lobby,
+            )
+            .await;
+        }
+        if in_applicable_lobby {
+            if last_game_state.is_some_and(|x| x.is_playing()) {
+                advance_playlist(
+                    au_capture_offsets_lock,
+                    au_process_lock,
+                    events,
+                    playlist_lock,
+                )
+                .await;
+            }
+            load_queued_preset(
+                au_capture_offsets_lock,
+                au_process_lock,
+                events,
+                queued_preset_lock,
+            )
+            .await;
+        }
+    }
+}
+
+async fn enforce_preset(
+    au_capture_offsets_lock: &RwLock<Option<AUCaptureOffsets>>,
+    au_process_lock: &RwLock<Option<AUProcess>>,
+    game_info_lock: &RwLock<GameInfo>,
+    enforcement_lock: &RwLock<Option<Enforcement>>,
+    enforce_log_lock: &RwLock<Vec<DriftEvent>>,
+    events: &Sender<Event>,
+) {
+    let mut interval = interval(Duration::from_secs(2));
+    loop {
+        interval.tick().await;
+        let enforcement = match *enforcement_lock.read().await {
+            None => continue,
+            Some(x) => x,
+        };
+        {
+            let game_info = game_info_lock.read().await;
+            if game_info.game_state != Some(GameState::Lobby)
+                || check_applicable(game_info.game_state, game_info.is_host).is_err()
+            {
+                continue;
+            }
+        }
+        let (expected, fields) = match Storage::load().and_then(|x| x.resolve(enforcement.preset)) {
+            Err(err) => {
+                // The preset will not resolve again by itself, so stop rather
+                // than report the same error on every tick.
+                error!("Enforce: {}", storage_error_message(&err));
+                {
+                    let mut enforcement_guard = enforcement_lock.write().await;
+                    if *enforcement_guard == Some(enforcement) {
+                        *enforcement_guard = None;
+                    }
+                }
+                emit(
+                    events,
+                    Event::Error(format!("Enforce: {}", storage_error_message(&err))),
+                );
+                emit(events, Event::AppStateChanged);
+                continue;
+            }
+            Ok(x) => x,
+        };
+        let actual = {
+            let au_capture_offsets_guard = au_capture_offsets_lock.read().await;
+            let au_process_guard = au_process_lock.read().await;
+            match au_process_guard
+                .as_ref()
+                .and_then(|x| AUProcessReadWrite::new(au_capture_offsets_guard.as_ref(), x))
+                .and_then(|x| x.game_settings())
+            {
+                None => continue,
+                Some(x) => x,
+            }
+        };
+        let drifted = drifted_fields(&expected, &actual, &fields);
+        if drifted.is_empty() {
+            continue;
+        }
+        warn!("Enforce: drifted {:?}", drifted);
+        record_drift(
+            &mut *enforce_log_lock.write().await,
+            &expected,
+            &actual,
+            &drifted,
+            enforcement.action,
+        );
+        emit(events, Event::SettingsDrifted(drifted));
+        if enforcement.action == EnforceAction::Reapply {
+            if let Err(err) = apply_game_settings(
+                au_capture_offsets_lock,
+                au_process_lock,
+                events,
+                expected,
+                &fields,
+            )
+            .await
+            {
+                error!("Enforce: apply failed. {:?}", err);
+                emit(
+                    events,
+                    Event::Error(format!("Enforce: apply failed. {:?}", err)),
+                );
+            }
+        }
+    }
+}
+
+/// Pushes the list whenever ausettings.json changes. While the file does not
+/// parse, subscribers keep the last good list and get an error instead.
+async fn watch_storage(events: &Sender<Event>) {
+    let mut revision = Storage::file_revision();
+    let mut interval = interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        let new_revision = Storage::file_revision();
+        if new_revision == revision {
+            continue;
+        }
+        revision = new_revision;
+        info!("Storage: reloading");
+        match Storage::load() {
+            Err(err) => {
+                error!("Storage: {}", storage_error_message(&err));
+                emit(
+                    events,
+                    Event::Error(format!("Storage: {}", storage_error_message(&err))),
+                );
+            }
+            Ok(storage) => emit(
+                events,
+                Event::StorageChanged {
+                    revision: storage.revision().map(|x| x.into()),
+                    game_settings_list: storage.game_settings_list,
+                },
+            ),
         }
     }
 }
 
 pub struct App {
     au_capture_offsets_url: String,
+    /// Shows developer tools such as the memory inspector.
+    debug: bool,
     _au_capture_offsets_task: JoinHandle<()>,
     _au_process_task: JoinHandle<()>,
+    _game_state_task: JoinHandle<()>,
+    _enforce_task: JoinHandle<()>,
+    _storage_task: JoinHandle<()>,
     au_capture_offsets: Arc<RwLock<Option<AUCaptureOffsets>>>,
     au_process: Arc<RwLock<Option<AUProcess>>>,
+    game_info: Arc<RwLock<GameInfo>>,
+    playlist: Arc<RwLock<Option<Playlist>>>,
+    queued_preset: Arc<RwLock<Option<usize>>>,
+    enforcement: Arc<RwLock<Option<Enforcement>>>,
+    enforce_log: Arc<RwLock<Vec<DriftEvent>>>,
+    /// The last memory inspection, compared against by the next one.
+    memory_snapshot: Arc<RwLock<Option<(Snapshot, MemoryDump)>>>,
+    /// The last status sent to subscribers.
+    process_status: Arc<RwLock<Option<ProcessStatus>>>,
+    events: Sender<Event>,
 }
 
 impl App {
-    pub fn new(process_status_sender: Sender<ProcessStatus>) -> Self {
+    pub fn new(debug: bool) -> Self {
         let au_capture_offsets_url =
             "https://raw.githubusercontent.com/denverquane/amonguscapture/master/Offsets.json";
         let au_capture_offsets = Arc::new(RwLock::new(None));
         let au_process = Arc::new(RwLock::new(None));
-        let (on_change_status, mut rx) = mpsc::channel::<()>(16);
+        let diagnostics = Arc::new(RwLock::new(Diagnostics::default()));
+        let game_info = Arc::new(RwLock::new(GameInfo::default()));
+        let playlist = Arc::new(RwLock::new(None));
+        let queued_preset = Arc::new(RwLock::new(None));
+        let recommended_preset = Arc::new(RwLock::new(None));
+        let enforcement = Arc::new(RwLock::new(None));
+        let enforce_log = Arc::new(RwLock::new(Vec::new()));
+        let process_status = Arc::new(RwLock::new(None));
+        let (events, mut rx) = broadcast::channel(64);
         spawn({
             let au_capture_offsets = au_capture_offsets.clone();
             let au_process = au_process.clone();
+            let diagnostics = diagnostics.clone();
+            let game_info = game_info.clone();
+            let queued_preset = queued_preset.clone();
+            let recommended_preset = recommended_preset.clone();
+            let enforcement = enforcement.clone();
+            let enforce_log = enforce_log.clone();
+            let process_status = process_status.clone();
+            let events = events.clone();
             async move {
                 loop {
-                    rx.recv().await.unwrap();
-                    println!("on_change_status");
-                    process_status_sender
-                        .send(ProcessStatus {
-                            au_capture_offsets: au_capture_offsets.read().await.is_some(),
-                            au_process: au_process.read().await.is_some(),
-                        })
-                        .await
-                        .unwrap();
+                    match rx.recv().await {
+                        Ok(Event::ProcessStatusChanged(_)) => continue,
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => return,
+                    }
+                    let au_capture_offsets_guard = au_capture_offsets.read().await;
+                    let au_process_guard = au_process.read().await;
+                    let offsets: Option<&AUCaptureOffsets> = au_capture_offsets_guard.as_ref();
+                    let process: Option<&AUProcess> = au_process_guard.as_ref();
+                    let diagnostics = diagnostics.read().await;
+                    let status = ProcessStatus {
+                        au_capture_offsets: offsets.is_some(),
+                        au_process: process.is_some(),
+                        pid: process.map(|x| x.process().pid()),
+                        exe_path: process.map(|x| x.exe_path().to_owned()),
+                        dll_hash: process.map(|x| x.dll_hash().to_owned()),
+                        dll_hash_known: match (offsets, process) {
+                            (Some(offsets), Some(process)) => offsets.contains(process.dll_hash()),
+                            _ => false,
+                        },
+                        game_options_scanned: process.is_some_and(|process| {
+                            process.scanned_game_options_offset().is_some()
+                                && offsets
+                                    .and_then(|x| x.game_options_offset(process.dll_hash()))
+                                    .is_none()
+                        }),
+                        game_version: process
+                            .and_then(|x| game_version::identify(x.dll_hash(), offsets)),
+                        offsets_source: au_capture_offsets_url.into(),
+                        offsets_fetched_at: diagnostics.offsets_fetched_at,
+                        offsets_error: diagnostics.offsets_error.clone(),
+                        capture_error: diagnostics.capture_error.clone(),
+                        game_info: game_info.read().await.clone(),
+                        queued_preset: *queued_preset.read().await,
+                        recommended_preset: *recommended_preset.read().await,
+                        enforcement: *enforcement.read().await,
+                        enforce_log: enforce_log.read().await.clone(),
+                    };
+                    *process_status.write().await = Some(status.clone());
+                    emit(&events, Event::ProcessStatusChanged(Box::new(status)));
                 }
             }
         });
 
         Self {
             au_capture_offsets_url: au_capture_offsets_url.into(),
+            debug,
             _au_capture_offsets_task: spawn({
                 let au_capture_offsets = au_capture_offsets.clone();
-                let on_change_status = on_change_status.clone();
+                let diagnostics = diagnostics.clone();
+                let events = events.clone();
                 async move {
                     fetch_offsets(
                         &au_capture_offsets,
-                        on_change_status,
+                        &diagnostics,
+                        &events,
                         au_capture_offsets_url,
                     )
                     .await
                 }
             }),
             _au_process_task: spawn({
+                let au_capture_offsets = au_capture_offsets.clone();
+                let au_process = au_process.clone();
+                let diagnostics = diagnostics.clone();
+                let events = events.clone();
+                async move {
+                    capture_process(&au_capture_offsets, &au_process, &diagnostics, &events).await
+                }
+            }),
+            _game_state_task: spawn({
+                let au_capture_offsets = au_capture_offsets.clone();
+                let au_process = au_process.clone();
+                let game_info = game_info.clone();
+                let playlist = playlist.clone();
+                let queued_preset = queued_preset.clone();
+                let recommended_preset = recommended_preset.clone();
+                let events = events.clone();
+                async move {
+                    watch_game_state(
+                        &au_capture_offsets,
+                        &au_process,
+                        &game_info,
+                        &playlist,
+                        &queued_preset,
+                        &recommended_preset,
+                        &events,
+                    )
+                    .await
+                }
+            }),
+            _enforce_task: spawn({
+                let au_capture_offsets = au_capture_offsets.clone();
                 let au_process = au_process.clone();
-                let on_change_status = on_change_status.clone();
-                async move { capture_process(&au_process, on_change_status).await }
+                let game_info = game_info.clone();
+                let enforcement = enforcement.clone();
+                let enforce_log = enforce_log.clone();
+                let events = events.clone();
+                async move {
+                    enforce_preset(
+                        &au_capture_offsets,
+                        &au_process,
+                        &game_info,
+                        &enforcement,
+                        &enforce_log,
+                        &events,
+                    )
+                    .await
+                }
+            }),
+            _storage_task: spawn({
+                let events = events.clone();
+                async move { watch_storage(&events).await }
             }),
             au_capture_offsets,
             au_process,
+            game_info,
+            playlist,
+            queued_preset,
+            enforcement,
+            enforce_log,
+            memory_snapshot: Arc::new(RwLock::new(None)),
+            process_status,
+            events,
         }
     }
 
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.events.subscribe()
+    }
+
     pub async fn init<'a>(&'a self) -> InitResponse<'a> {
+        let storage = Storage::load().unwrap_or_else(|err| {
+            // Saving stays refused until the file parses.
+            error!("Storage: {}", storage_error_message(&err));
+            emit(
+                &self.events,
+                Event::Error(format!("Storage: {}", storage_error_message(&err))),
+            );
+            Storage::default()
+        });
         InitResponse {
             au_offsets_repository_url: &self.au_capture_offsets_url,
-            game_settings_list: Storage::load().game_settings_list,
+            revision: storage.revision().map(|x| x.into()),
+            game_settings_list: storage.game_settings_list,
+            builtin_game_settings_list: builtin_presets(),
+            auto_apply_recommended: storage.auto_apply_recommended,
+            debug: self.debug,
         }
     }
 
-    pub fn open_browser(&self, url: &str) {
-        webbrowser::open(url).unwrap();
+    pub fn open_browser(&self, url: &str) -> Result<(), ApplyError> {
+        webbrowser::open(url)
+            .map(|_| ())
+            .map_err(|err| ApplyError::Error(format!("Failed to open the browser ({})", err)))
     }
 
-    pub fn set_game_settings_name(&self, idx: usize, name: String) -> bool {
-        let mut storage = Storage::load();
-        storage.game_settings_list[idx].name = name;
-        match storage.save() {
-            Err(err) => {
-                eprintln!("Error: file output failed. {}", err);
-                return false;
-            }
-            Ok(_) => {}
-        };
-        true
+    pub fn set_game_settings_name(
+        &self,
+        idx: usize,
+        name: String,
+        revision: Option<&str>,
+    ) -> Result<String, ApplyError> {
+        Ok(Storage::update(revision, |storage| {
+            storage.rename(idx, name)
+        })?)
+    }
+
+    pub fn set_game_settings_parent(
+        &self,
+        idx: usize,
+        parent: Option<String>,
+        revision: Option<&str>,
+    ) -> Result<String, ApplyError> {
+        Ok(Storage::update(revision, |storage| {
+            storage.slot_mut(idx)?.parent = parent;
+            storage.validate_parent(idx)
+        })?)
+    }
+
+    pub fn set_player_count_range(
+        &self,
+        idx: usize,
+        range: Option<PlayerCountRange>,
+        revision: Option<&str>,
+    ) -> Result<String, ApplyError> {
+        Ok(Storage::update(revision, |storage| {
+            storage.slot_mut(idx)?.player_count = range;
+            Ok(())
+        })?)
+    }
+
+    pub fn set_auto_apply_recommended(
+        &self,
+        value: bool,
+        revision: Option<&str>,
+    ) -> Result<String, ApplyError> {
+        Ok(Storage::update(revision, |storage| {
+            storage.auto_apply_recommended = value;
+            Ok(())
+        })?)
     }
 
-    pub async fn save_memory_to_file(&self, idx: usize) -> Option<()> {
-        let game_settings = {
+    pub async fn save_memory_to_file(
+        &self,
+        idx: usize,
+        fields: Option<Vec<GameSettingsField>>,
+        revision: Option<&str>,
+    ) -> Result<String, ApplyError> {
+        let unavailable = || ApplyError::Error("Among Us process is not available".into());
+        let (game_settings, game_version) = {
             let au_capture_offsets_guard = self.au_capture_offsets.read().await;
-            let au_capture_offsets = au_capture_offsets_guard.as_ref()?;
+            let au_capture_offsets = au_capture_offsets_guard.as_ref();
             let au_process_guard = self.au_process.read().await;
-            let au_process = au_process_guard.as_ref()?;
-            AUProcessReadWrite::new(au_capture_offsets, au_process)?.game_settings()
+            let au_process = au_process_guard.as_ref().ok_or_else(unavailable)?;
+            (
+                AUProcessReadWrite::new(au_capture_offsets, au_process)
+                    .ok_or_else(unavailable)?
+                    .game_settings()
+                    .ok_or_else(unavailable)?,
+                game_version::identify(au_process.dll_hash(), au_capture_offsets),
+            )
         };
-        let mut storage = Storage::load();
-        storage.game_settings_list[idx].game_settings = Some(game_settings);
-        match storage.save() {
-            Err(_err) => {
-                eprintln!("Error: file output failed.");
-                return None;
+        Ok(Storage::update(revision, |storage| {
+            let item = storage.slot_mut(idx)?;
+            item.game_settings = Some(game_settings);
+            item.game_version = game_version;
+            item.fields = fields;
+            Ok(())
+        })?)
+    }
+
+    /// Applies the preset at `idx`. While a match is in progress the preset
+    /// is either refused or, with `queue`, applied on return to the lobby.
+    pub async fn load_memory_from_file(&self, idx: usize, queue: bool) -> Result<(), ApplyError> {
+        Storage::load()?.slot(idx)?;
+        match self.ensure_applicable().await {
+            Err(ApplyError::NotInLobby) if queue => {
+                *(self.queued_preset.write().await) = Some(idx);
+                emit(&self.events, Event::AppStateChanged);
+                Ok(())
             }
-            Ok(_) => {}
+            Err(err) => Err(err),
+            Ok(_) => {
+                load_preset(
+                    &self.au_capture_offsets,
+                    &self.au_process,
+                    &self.events,
+                    idx,
+                )
+                .await
+            }
+        }
+    }
+
+    pub async fn cancel_queued_preset(&self) {
+        *(self.queued_preset.write().await) = None;
+        emit(&self.events, Event::AppStateChanged);
+    }
+
+    pub async fn load_builtin_preset(&self, builtin_idx: usize) -> Result<(), ApplyError> {
+        self.ensure_applicable().await?;
+        let item = builtin_presets()
+            .into_iter()
+            .nth(builtin_idx)
+            .ok_or(ApplyError::NoSuchPreset(builtin_idx))?;
+        let fields = item.fields().to_vec();
+        let name = item.name;
+        let game_settings = item
+            .game_settings
+            .ok_or_else(|| ApplyError::Error(format!("No data ({})", name)))?;
+        apply_game_settings(
+            &self.au_capture_offsets,
+            &self.au_process,
+            &self.events,
+            game_settings,
+            &fields,
+        )
+        .await
+    }
+
+    pub fn copy_builtin_preset(
+        &self,
+        builtin_idx: usize,
+        idx: usize,
+        revision: Option<&str>,
+    ) -> Result<String, ApplyError> {
+        let item = builtin_presets()
+            .into_iter()
+            .nth(builtin_idx)
+            .ok_or(ApplyError::NoSuchPreset(builtin_idx))?;
+        Ok(Storage::update(revision, |storage| {
+            storage.rename(idx, item.name.clone())?;
+            *storage.slot_mut(idx)? = item;
+            Ok(())
+        })?)
+    }
+
+    pub async fn apply_random_game_settings(
+        &self,
+        constraints: &RandomizerConstraints,
+    ) -> Result<GameSettings, ApplyError> {
+        self.ensure_applicable().await?;
+        let game_settings = randomizer::generate(constraints).map_err(|err| {
+            ApplyError::Error(match err {
+                RandomizerError::EmptyRange(field) => format!("No legal value ({:?})", field),
+                RandomizerError::TooManyTasks => "Task limit cannot be met".into(),
+                RandomizerError::Invalid(fields) => format!("Invalid value ({:?})", fields),
+            })
+        })?;
+        apply_game_settings(
+            &self.au_capture_offsets,
+            &self.au_process,
+            &self.events,
+            game_settings.clone(),
+            &GameSettingsField::ALL,
+        )
+        .await?;
+        Ok(game_settings)
+    }
+
+    pub async fn start_playlist(
+        &self,
+        indices: Vec<usize>,
+        shuffle: bool,
+    ) -> Result<(), ApplyError> {
+        let storage = Storage::load()?;
+        for idx in &indices {
+            storage.slot(*idx)?;
+        }
+        let mut playlist = Playlist::new(indices, shuffle);
+        // Mid-match the first preset is left to the return to the lobby.
+        let result = self.ensure_applicable().await;
+        let idx = match result {
+            Ok(_) => playlist.next(),
+            Err(_) => None,
         };
-        Some(())
-    }
-
-    pub async fn load_memory_from_file(&self, idx: usize) -> Option<()> {
-        let au_capture_offsets_guard = self.au_capture_offsets.read().await;
-        let au_capture_offsets = au_capture_offsets_guard.as_ref()?;
-        let au_process_guard = self.au_process.read().await;
-        let au_process = au_process_guard.as_ref()?;
-        let mut storage = Storage::load();
-        let game_settings = match storage.game_settings_list.remove(idx).game_settings {
-            None => {
-                eprintln!("Error: No data.");
-                return None;
+        *(self.playlist.write().await) = Some(playlist);
+        match (result, idx) {
+            (Err(ApplyError::NotInLobby), _) | (Ok(_), None) => Ok(()),
+            (Err(err), _) => Err(err),
+            (Ok(_), Some(idx)) => {
+                load_preset(
+                    &self.au_capture_offsets,
+                    &self.au_process,
+                    &self.events,
+                    idx,
+                )
+                .await
+            }
+        }
+    }
+
+    pub async fn stop_playlist(&self) {
+        *(self.playlist.write().await) = None;
+    }
+
+    /// Locks the game settings to the preset at `idx` while hosting a lobby.
+    pub async fn start_enforce(&self, idx: usize, action: EnforceAction) -> Result<(), ApplyError> {
+        Storage::load()?.resolve(idx)?;
+        *(self.enforcement.write().await) = Some(Enforcement {
+            preset: idx,
+            action,
+        });
+        emit(&self.events, Event::AppStateChanged);
+        Ok(())
+    }
+
+    pub async fn stop_enforce(&self) {
+        *(self.enforcement.write().await) = None;
+        emit(&self.events, Event::AppStateChanged);
+    }
+
+    pub async fn clear_enforce_log(&self) {
+        self.enforce_log.write().await.clear();
+        emit(&self.events, Event::AppStateChanged);
+    }
+
+    pub fn history(&self, query: &HistoryQuery) -> Result<Vec<MatchRecord>, ApplyError> {
+        history::query(query).map_err(|err| {
+            error!("History: file input failed. {}", err);
+            ApplyError::Error(format!("Failed to read history ({})", err))
+        })
+    }
+
+    /// Writes the matching history to `path` and returns the record count.
+    pub fn export_history(&self, query: &HistoryQuery, path: &str) -> Result<usize, ApplyError> {
+        history::export(query, std::path::Path::new(path)).map_err(|err| {
+            error!("History: file output failed. {}", err);
+            ApplyError::Error(format!("Failed to export history ({})", err))
+        })
+    }
+
+    pub async fn lobby_info(&self) -> Option<LobbyInfo> {
+        self.game_info.read().await.lobby.clone()
+    }
+
+    /// Writes a zip for bug reports to `path`, or into the data directory,
+    /// and returns where it went.
+    pub async fn export_diagnostics(&self, path: Option<String>) -> Result<String, ApplyError> {
+        let (modules, pointer_chain) = {
+            let au_capture_offsets_guard = self.au_capture_offsets.read().await;
+            let au_process_guard = self.au_process.read().await;
+            match (au_capture_offsets_guard.as_ref(), au_process_guard.as_ref()) {
+                (_, None) => (Vec::new(), None),
+                (au_capture_offsets, Some(au_process)) => (
+                    au_process.process().modules(),
+                    AUProcessReadWrite::new(au_capture_offsets, au_process)
+                        .and_then(|x| x.pointer_chain()),
+                ),
             }
-            Some(x) => x,
         };
-        AUProcessReadWrite::new(au_capture_offsets, au_process)?.set_game_settings(game_settings);
-        Some(())
+        let bundle = Bundle::new(
+            self.process_status.read().await.clone(),
+            modules,
+            pointer_chain,
+        );
+        let path = path.map_or_else(diagnostics::default_path, PathBuf::from);
+        diagnostics::export(&bundle, &path).map_err(|err| {
+            error!("Diagnostics: file output failed. {}", err);
+            ApplyError::Error(format!("Failed to export diagnostics ({})", err))
+        })?;
+        info!("Diagnostics: exported to {}", path.display());
+        Ok(path.to_string_lossy().into_owned())
     }
+
+    /// Dumps the `GameSettings` region and marks the bytes that changed since
+    /// the last call.
+    pub async fn inspect_memory(&self) -> Result<MemoryDump, ApplyError> {
+        let unavailable = || ApplyError::Error("Among Us process is not available".into());
+        let (snapshot, layout) = {
+            let au_capture_offsets_guard = self.au_capture_offsets.read().await;
+            let au_process_guard = self.au_process.read().await;
+            let au_process = au_process_guard.as_ref().ok_or_else(unavailable)?;
+            let au_process_read_write =
+                AUProcessReadWrite::new(au_capture_offsets_guard.as_ref(), au_process)
+                    .ok_or_else(unavailable)?;
+            let (base_addr, bytes) = au_process_read_write
+                .game_settings_bytes(REGION_SIZE)
+                .ok_or_else(unavailable)?;
+            (
+                Snapshot { base_addr, bytes },
+                au_process_read_write.game_settings_layout(),
+            )
+        };
+        let mut memory_snapshot = self.memory_snapshot.write().await;
+        let dump = inspector::dump(&snapshot, memory_snapshot.as_ref().map(|(x, _)| x), &layout);
+        *memory_snapshot = Some((snapshot, dump.clone()));
+        Ok(dump)
+    }
+
+    /// Writes the last memory inspection to `path` as JSON.
+    pub async fn export_memory_dump(&self, path: &str) -> Result<(), ApplyError> {
+        let memory_snapshot = self.memory_snapshot.read().await;
+        let (_, dump) = memory_snapshot
+            .as_ref()
+            .ok_or_else(|| ApplyError::Error("Memory has not been inspected yet".into()))?;
+        inspector::export(dump, std::path::Path::new(path)).map_err(|err| {
+            error!("Inspector: file output failed. {}", err);
+            ApplyError::Error(format!("Failed to export memory dump ({})", err))
+        })
+    }
+
+    async fn ensure_applicable(&self) -> Result<(), ApplyError> {
+        let game_info = self.game_info.read().await;
+        check_applicable(game_info.game_state, game_info.is_host)
+    }
+}
+
+#[test]
+fn test_check_applicable() {
+    assert!(check_applicable(None, None).is_ok());
+    assert!(check_applicable(Some(GameState::Menu), Some(false)).is_ok());
+    assert!(check_applicable(Some(GameState::Lobby), Some(true)).is_ok());
+    assert!(check_applicable(Some(GameState::Lobby), None).is_ok());
+    assert!(matches!(
+        check_applicable(Some(GameState::Lobby), Some(false)),
+        Err(ApplyError::NotHost)
+    ));
+    assert!(matches!(
+        check_applicable(Some(GameState::Meeting), Some(true)),
+        Err(ApplyError::NotInLobby)
+    ));
 }
 
 #[test]
@@ -1,3 +1,4 @@
+use log::{debug, info};
 use serde_json::Value;
 
 pub enum AUCaptureOffsetsError {
@@ -11,18 +12,103 @@ pub struct AUCaptureOffsets {
 
 impl AUCaptureOffsets {
     pub fn fetch(url: &str) -> Result<Self, AUCaptureOffsetsError> {
+        debug!("Fetching offsets from {}", url);
         let resp = reqwest::blocking::get(url)
-            .map_err(|err| AUCaptureOffsetsError::FetchFailed(err))?
+            .map_err(AUCaptureOffsetsError::FetchFailed)?
             .text()
-            .map_err(|err| AUCaptureOffsetsError::FetchFailed(err))?;
-        let json: Value =
-            json5::from_str(&resp).map_err(|err| AUCaptureOffsetsError::ParseFailed(err))?;
+            .map_err(AUCaptureOffsetsError::FetchFailed)?;
+        let json: Value = json5::from_str(&resp).map_err(AUCaptureOffsetsError::ParseFailed)?;
+        info!(
+            "Fetched offsets for {} game versions",
+            json.as_object().map_or(0, |x| x.len())
+        );
         Ok(Self { json })
     }
 
+    /// Whether the offsets cover the GameAssembly.dll with this hash.
+    pub fn contains(&self, sha256: &str) -> bool {
+        self.json.get(sha256).is_some()
+    }
+
+    pub fn description(&self, sha256: &str) -> Option<&str> {
+        self.json[sha256]["Description"].as_str()
+    }
+
+    pub fn is_epic(&self, sha256: &str) -> Option<bool> {
+        self.json[sha256]["isEpic"].as_bool()
+    }
+
     pub fn game_options_offset(&self, sha256: &str) -> Option<u32> {
         self.json[sha256]["GameOptionsOffset"]
             .as_u64()
             .map(|x| x as u32)
     }
+
+    pub fn game_state_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "GameStateOffsets")
+    }
+
+    pub fn meeting_hud_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "MeetingHudPtr")
+    }
+
+    pub fn meeting_hud_cache_ptr_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "MeetingHudCachePtrOffsets")
+    }
+
+    pub fn meeting_hud_state_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "MeetingHudStateOffsets")
+    }
+
+    pub fn host_id_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "HostIdOffsets")
+    }
+
+    pub fn client_id_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "ClientIdOffsets")
+    }
+
+    pub fn game_code_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "GameCodeOffsets")
+    }
+
+    pub fn string_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "StringOffsets")
+    }
+
+    pub fn all_player_ptr_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "AllPlayerPtrOffsets")
+    }
+
+    pub fn all_players_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "AllPlayersOffsets")
+    }
+
+    pub fn player_count_offsets(&self, sha256: &str) -> Option<Vec<u32>> {
+        self.u32_array(sha256, "PlayerCountOffsets")
+    }
+
+    pub fn add_player_ptr(&self, sha256: &str) -> Option<u32> {
+        self.json[sha256]["AddPlayerPtr"].as_u64().map(|x| x as u32)
+    }
+
+    pub fn player_list_ptr(&self, sha256: &str) -> Option<u32> {
+        self.json[sha256]["PlayerListPtr"]
+            .as_u64()
+            .map(|x| x as u32)
+    }
+
+    pub fn player_info_offset(&self, sha256: &str, field: &str) -> Option<u32> {
+        self.json[sha256]["PlayerInfoStructOffsets"][field]
+            .as_u64()
+            .map(|x| x as u32)
+    }
+
+    fn u32_array(&self, sha256: &str, key: &str) -> Option<Vec<u32>> {
+        self.json[sha256][key]
+            .as_array()?
+            .iter()
+            .map(|x| x.as_u64().map(|x| x as u32))
+            .collect()
+    }
 }
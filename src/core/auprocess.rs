@@ -1,6 +1,7 @@
-use std::{fs, io, path::Path};
+use std::{fs, io, path::Path, sync::Arc};
 
 use anyhow::Result;
+use log::debug;
 use sha2::{Digest, Sha256};
 
 use super::process::Process;
@@ -20,20 +21,34 @@ pub enum AUProcessError {
 }
 
 pub struct AUProcess {
-    process: Process,
+    /// Shared with the signature scan, which runs on a blocking thread.
+    process: Arc<Process>,
+    exe_path: String,
+    dll_path: String,
     dll_hash: String,
+    /// Whether the signature scan has run for this process.
+    scanned: bool,
+    /// `GameOptionsOffset` found by signature scanning, for versions that
+    /// Offsets.json does not cover yet.
+    scanned_game_options_offset: Option<u32>,
 }
 
 impl AUProcess {
     pub fn new() -> Result<Self, AUProcessError> {
         let process = Process::find("Among Us.exe").ok_or(AUProcessError::ProcessNotFound)?;
-        let dll_path = game_assembly_dll_path(&process.path());
+        let exe_path = process.path().trim_end_matches('\0').to_owned();
+        let dll_path = game_assembly_dll_path(&exe_path);
+        debug!("Hashing {}", dll_path);
         let mut hasher = Sha256::new();
-        let dll_vec = fs::read(&dll_path).map_err(|x| AUProcessError::DllNotFound(x))?;
-        hasher.update(dll_vec); // HEAVY!!
+        let dll_vec = fs::read(&dll_path).map_err(AUProcessError::DllNotFound)?;
+        hasher.update(&dll_vec); // HEAVY!!
         Ok(Self {
-            process,
+            process: Arc::new(process),
+            exe_path,
+            dll_path,
             dll_hash: hex::encode_upper(hasher.finalize()),
+            scanned: false,
+            scanned_game_options_offset: None,
         })
     }
 
@@ -41,7 +56,32 @@ impl AUProcess {
         &self.process
     }
 
+    pub fn shared_process(&self) -> Arc<Process> {
+        self.process.clone()
+    }
+
+    pub fn dll_path(&self) -> &str {
+        &self.dll_path
+    }
+
+    pub fn exe_path(&self) -> &str {
+        &self.exe_path
+    }
+
     pub fn dll_hash(&self) -> &str {
         &self.dll_hash
     }
+
+    pub fn scanned(&self) -> bool {
+        self.scanned
+    }
+
+    pub fn scanned_game_options_offset(&self) -> Option<u32> {
+        self.scanned_game_options_offset
+    }
+
+    pub fn set_scanned_game_options_offset(&mut self, offset: Option<u32>) {
+        self.scanned = true;
+        self.scanned_game_options_offset = offset;
+    }
 }
@@ -1,12 +1,89 @@
+use log::trace;
+use serde::Serialize;
+
 use super::{
-    aucaptureoffsets::AUCaptureOffsets, auprocess::AUProcess, game_settings::GameSettings,
+    aucaptureoffsets::AUCaptureOffsets,
+    auprocess::AUProcess,
+    game_settings::{GameSettings, GameSettingsField},
+    game_state::GameState,
+    lobby::{parse_game_code, LobbyInfo, PlayerInfo},
     process::Process,
 };
 
 const ENABLE_UNCONTROLLABLE_PARAMS: bool = false;
 
+struct GameStateOffsets {
+    game_state: Vec<u32>,
+    meeting_hud: Vec<u32>,
+    meeting_hud_cache_ptr: Vec<u32>,
+    meeting_hud_state: Vec<u32>,
+}
+
+impl GameStateOffsets {
+    fn new(au_capture_offsets: &AUCaptureOffsets, dll_hash: &str) -> Option<Self> {
+        Some(Self {
+            game_state: au_capture_offsets.game_state_offsets(dll_hash)?,
+            meeting_hud: au_capture_offsets.meeting_hud_offsets(dll_hash)?,
+            meeting_hud_cache_ptr: au_capture_offsets.meeting_hud_cache_ptr_offsets(dll_hash)?,
+            meeting_hud_state: au_capture_offsets.meeting_hud_state_offsets(dll_hash)?,
+        })
+    }
+}
+
+struct HostOffsets {
+    host_id: Vec<u32>,
+    client_id: Vec<u32>,
+}
+
+impl HostOffsets {
+    fn new(au_capture_offsets: &AUCaptureOffsets, dll_hash: &str) -> Option<Self> {
+        Some(Self {
+            host_id: au_capture_offsets.host_id_offsets(dll_hash)?,
+            client_id: au_capture_offsets.client_id_offsets(dll_hash)?,
+        })
+    }
+}
+
+struct LobbyOffsets {
+    game_code: Vec<u32>,
+    string: Vec<u32>,
+    all_player_ptr: Vec<u32>,
+    all_players: Vec<u32>,
+    player_count: Vec<u32>,
+    add_player_ptr: u32,
+    player_list_ptr: u32,
+    player_id: u32,
+    player_name: u32,
+    color_id: u32,
+}
+
+impl LobbyOffsets {
+    fn new(au_capture_offsets: &AUCaptureOffsets, dll_hash: &str) -> Option<Self> {
+        Some(Self {
+            game_code: au_capture_offsets.game_code_offsets(dll_hash)?,
+            string: au_capture_offsets.string_offsets(dll_hash)?,
+            all_player_ptr: au_capture_offsets.all_player_ptr_offsets(dll_hash)?,
+            all_players: au_capture_offsets.all_players_offsets(dll_hash)?,
+            player_count: au_capture_offsets.player_count_offsets(dll_hash)?,
+            add_player_ptr: au_capture_offsets.add_player_ptr(dll_hash)?,
+            player_list_ptr: au_capture_offsets.player_list_ptr(dll_hash)?,
+            player_id: au_capture_offsets.player_info_offset(dll_hash, "PlayerIDOffset")?,
+            player_name: au_capture_offsets.player_info_offset(dll_hash, "PlayerNameOffset")?,
+            color_id: au_capture_offsets.player_info_offset(dll_hash, "ColorIDOffset")?,
+        })
+    }
+}
+
+/// Upper bound for player lists read from memory, guarding against garbage.
+const MAX_PLAYERS: i32 = 100;
+/// Upper bound for strings read from memory, guarding against garbage.
+const MAX_STRING_LENGTH: i32 = 256;
+
 struct Offsets {
     game_options_offset: u32,
+    game_state: Option<GameStateOffsets>,
+    host: Option<HostOffsets>,
+    lobby: Option<LobbyOffsets>,
     game_settings_relative_address: [u32; 2],
     map: u32,
     player_speed: u32,
@@ -32,6 +109,9 @@ impl Offsets {
     pub fn new(game_options_offset: u32) -> Self {
         Self {
             game_options_offset,
+            game_state: None,
+            host: None,
+            lobby: None,
             game_settings_relative_address: [0x5C, 0x04],
             map: 0x10,
             player_speed: 0x14,    // float
@@ -55,89 +135,222 @@ impl Offsets {
     }
 }
 
-fn base_addr(process: &Process, offsets: &Offsets) -> u32 {
-    let game_options = process.read_u32(
-        process
-            .base_addr_of_module_name("GameAssembly.dll")
-            .unwrap()
-            + offsets.game_options_offset,
-    );
+impl Offsets {
+    /// Offset of each field from the `GameSettings` base address.
+    fn layout(&self) -> Vec<(GameSettingsField, u32)> {
+        use GameSettingsField::*;
+        vec![
+            (Map, self.map),
+            (PlayerSpeed, self.player_speed),
+            (CrewmateVision, self.crewmate_vision),
+            (ImpostorVision, self.impostor_vision),
+            (KillCooldown, self.kill_cooldown),
+            (CommonTasks, self.common_tasks),
+            (LongTasks, self.long_tasks),
+            (ShortTasks, self.short_tasks),
+            (EmergencyMeeting, self.emergency_meeting),
+            (EmergencyCooldown, self.emergency_cooldown),
+            (Impostors, self.impostors),
+            (KillDistance, self.kill_distance),
+            (DiscussionTime, self.discussion_time),
+            (VotingTime, self.voting_time),
+            (ConfirmEject, self.confirm_eject),
+            (VisualTasks, self.visual_tasks),
+            (AnonymousVoting, self.anonymous_voting),
+            (TaskBarUpdates, self.task_bar_updates),
+        ]
+    }
+}
+
+/// Follows a pointer chain the way AmongUsCapture does: every offset but the
+/// last is dereferenced, and the last is added.
+fn resolve_pointer_chain(process: &Process, base: u32, offsets: &[u32]) -> Option<u32> {
+    let (last, init) = offsets.split_last()?;
+    let mut addr = base;
+    for offset in init {
+        addr = process.read_u32(addr.wrapping_add(*offset));
+        if addr == 0 {
+            return None;
+        }
+    }
+    Some(addr.wrapping_add(*last))
+}
+
+/// Reads an Il2Cpp `System.String` whose length and UTF-16 characters sit at
+/// `string_offsets[0]` and `string_offsets[1]`.
+fn read_string(process: &Process, addr: u32, string_offsets: &[u32]) -> Option<String> {
+    if addr == 0 || string_offsets.len() < 2 {
+        return None;
+    }
+    let len = process.read_i32(addr.wrapping_add(string_offsets[0]));
+    if !(0..=MAX_STRING_LENGTH).contains(&len) {
+        return None;
+    }
+    let bytes = process.read_bytes(addr.wrapping_add(string_offsets[1]), len as usize * 2);
+    let utf16: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&utf16))
+}
+
+/// Addresses visited on the way from GameAssembly.dll to `GameSettings`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointerChain {
+    pub game_assembly: u32,
+    pub game_options_offset: u32,
+    pub game_options: u32,
+    pub game_settings_relative_address: [u32; 2],
+    pub game_settings: u32,
+}
+
+/// `None` once GameAssembly.dll is unloaded, e.g. while the game exits.
+fn pointer_chain(process: &Process, offsets: &Offsets) -> Option<PointerChain> {
+    let game_assembly = process.base_addr_of_module_name("GameAssembly.dll")?;
+    let game_options = process.read_u32(game_assembly.wrapping_add(offsets.game_options_offset));
     // let game_options = process.read_u32(game_options_ref);
     let mut addr = game_options;
     for relative in offsets.game_settings_relative_address.iter() {
-        addr = process.read_u32(addr + relative);
-    }
-    println!(
-        "{:x} {:x} {:x} {:x} {:?}",
-        process
-            .base_addr_of_module_name("GameAssembly.dll")
-            .unwrap(),
-        offsets.game_options_offset,
+        addr = process.read_u32(addr.wrapping_add(*relative));
+    }
+    let pointer_chain = PointerChain {
+        game_assembly,
+        game_options_offset: offsets.game_options_offset,
         game_options,
-        addr,
-        offsets.game_settings_relative_address
-    );
-    addr
+        game_settings_relative_address: offsets.game_settings_relative_address,
+        game_settings: addr,
+    };
+    trace!("{:x?}", pointer_chain);
+    Some(pointer_chain)
+}
+
+fn base_addr(process: &Process, offsets: &Offsets) -> Option<u32> {
+    pointer_chain(process, offsets).map(|x| x.game_settings)
 }
 
 impl GameSettings {
-    fn read(process: &Process, offsets: &Offsets) -> Self {
-        let base_addr = base_addr(process, offsets);
-        Self {
-            map: process.read_i32(base_addr + offsets.map),
-            player_speed: process.read_f32(base_addr + offsets.player_speed),
-            crewmate_vision: process.read_f32(base_addr + offsets.crewmate_vision),
-            impostor_vision: process.read_f32(base_addr + offsets.impostor_vision),
-            kill_cooldown: process.read_f32(base_addr + offsets.kill_cooldown),
-            common_tasks: process.read_i32(base_addr + offsets.common_tasks),
-            long_tasks: process.read_i32(base_addr + offsets.long_tasks),
-            short_tasks: process.read_i32(base_addr + offsets.short_tasks),
-            emergency_meeting: process.read_i32(base_addr + offsets.emergency_meeting),
-            emergency_cooldown: process.read_i32(base_addr + offsets.emergency_cooldown),
-            impostors: process.read_i32(base_addr + offsets.impostors),
-            kill_distance: process.read_i32(base_addr + offsets.kill_distance),
-            discussion_time: process.read_i32(base_addr + offsets.discussion_time),
-            voting_time: process.read_i32(base_addr + offsets.voting_time),
-            confirm_eject: process.read_u8(base_addr + offsets.confirm_eject) != 0,
-            visual_tasks: process.read_u8(base_addr + offsets.visual_tasks) != 0,
-            anonymous_voting: process.read_u8(base_addr + offsets.anonymous_voting) != 0,
-            task_bar_updates: process.read_i32(base_addr + offsets.task_bar_updates),
-        }
+    fn read(process: &Process, offsets: &Offsets) -> Option<Self> {
+        let base_addr = base_addr(process, offsets)?;
+        Some(Self {
+            map: process.read_i32(base_addr.wrapping_add(offsets.map)),
+            player_speed: process.read_f32(base_addr.wrapping_add(offsets.player_speed)),
+            crewmate_vision: process.read_f32(base_addr.wrapping_add(offsets.crewmate_vision)),
+            impostor_vision: process.read_f32(base_addr.wrapping_add(offsets.impostor_vision)),
+            kill_cooldown: process.read_f32(base_addr.wrapping_add(offsets.kill_cooldown)),
+            common_tasks: process.read_i32(base_addr.wrapping_add(offsets.common_tasks)),
+            long_tasks: process.read_i32(base_addr.wrapping_add(offsets.long_tasks)),
+            short_tasks: process.read_i32(base_addr.wrapping_add(offsets.short_tasks)),
+            emergency_meeting: process.read_i32(base_addr.wrapping_add(offsets.emergency_meeting)),
+            emergency_cooldown: process
+                .read_i32(base_addr.wrapping_add(offsets.emergency_cooldown)),
+            impostors: process.read_i32(base_addr.wrapping_add(offsets.impostors)),
+            kill_distance: process.read_i32(base_addr.wrapping_add(offsets.kill_distance)),
+            discussion_time: process.read_i32(base_addr.wrapping_add(offsets.discussion_time)),
+            voting_time: process.read_i32(base_addr.wrapping_add(offsets.voting_time)),
+            confirm_eject: process.read_u8(base_addr.wrapping_add(offsets.confirm_eject)) != 0,
+            visual_tasks: process.read_u8(base_addr.wrapping_add(offsets.visual_tasks)) != 0,
+            anonymous_voting: process.read_u8(base_addr.wrapping_add(offsets.anonymous_voting))
+                != 0,
+            task_bar_updates: process.read_i32(base_addr.wrapping_add(offsets.task_bar_updates)),
+        })
     }
 
-    fn write(&self, process: &Process, offsets: &Offsets) {
-        let base_addr = base_addr(process, offsets);
-        if ENABLE_UNCONTROLLABLE_PARAMS {
-            process.write_i32(base_addr + offsets.map, self.map);
+    fn write(
+        &self,
+        process: &Process,
+        offsets: &Offsets,
+        fields: &[GameSettingsField],
+    ) -> Option<()> {
+        let base_addr = base_addr(process, offsets)?;
+        for field in fields {
+            self.write_field(process, offsets, base_addr, *field);
         }
-        process.write_f32(base_addr + offsets.player_speed, self.player_speed);
-        process.write_f32(base_addr + offsets.crewmate_vision, self.crewmate_vision);
-        process.write_f32(base_addr + offsets.impostor_vision, self.impostor_vision);
-        process.write_f32(base_addr + offsets.kill_cooldown, self.kill_cooldown);
-        process.write_i32(base_addr + offsets.common_tasks, self.common_tasks);
-        process.write_i32(base_addr + offsets.long_tasks, self.long_tasks);
-        process.write_i32(base_addr + offsets.short_tasks, self.short_tasks);
-        process.write_i32(
-            base_addr + offsets.emergency_meeting,
-            self.emergency_meeting,
-        );
-        process.write_i32(
-            base_addr + offsets.emergency_cooldown,
-            self.emergency_cooldown,
-        );
-        if ENABLE_UNCONTROLLABLE_PARAMS {
-            process.write_i32(base_addr + offsets.impostors, self.impostors);
+        Some(())
+    }
+
+    fn write_field(
+        &self,
+        process: &Process,
+        offsets: &Offsets,
+        base_addr: u32,
+        field: GameSettingsField,
+    ) {
+        match field {
+            GameSettingsField::Map => {
+                if ENABLE_UNCONTROLLABLE_PARAMS {
+                    process.write_i32(base_addr.wrapping_add(offsets.map), self.map);
+                }
+            }
+            GameSettingsField::PlayerSpeed => process.write_f32(
+                base_addr.wrapping_add(offsets.player_speed),
+                self.player_speed,
+            ),
+            GameSettingsField::CrewmateVision => process.write_f32(
+                base_addr.wrapping_add(offsets.crewmate_vision),
+                self.crewmate_vision,
+            ),
+            GameSettingsField::ImpostorVision => process.write_f32(
+                base_addr.wrapping_add(offsets.impostor_vision),
+                self.impostor_vision,
+            ),
+            GameSettingsField::KillCooldown => process.write_f32(
+                base_addr.wrapping_add(offsets.kill_cooldown),
+                self.kill_cooldown,
+            ),
+            GameSettingsField::CommonTasks => process.write_i32(
+                base_addr.wrapping_add(offsets.common_tasks),
+                self.common_tasks,
+            ),
+            GameSettingsField::LongTasks => {
+                process.write_i32(base_addr.wrapping_add(offsets.long_tasks), self.long_tasks)
+            }
+            GameSettingsField::ShortTasks => process.write_i32(
+                base_addr.wrapping_add(offsets.short_tasks),
+                self.short_tasks,
+            ),
+            GameSettingsField::EmergencyMeeting => process.write_i32(
+                base_addr.wrapping_add(offsets.emergency_meeting),
+                self.emergency_meeting,
+            ),
+            GameSettingsField::EmergencyCooldown => process.write_i32(
+                base_addr.wrapping_add(offsets.emergency_cooldown),
+                self.emergency_cooldown,
+            ),
+            GameSettingsField::Impostors => {
+                if ENABLE_UNCONTROLLABLE_PARAMS {
+                    process.write_i32(base_addr.wrapping_add(offsets.impostors), self.impostors);
+                }
+            }
+            GameSettingsField::KillDistance => process.write_i32(
+                base_addr.wrapping_add(offsets.kill_distance),
+                self.kill_distance,
+            ),
+            GameSettingsField::DiscussionTime => process.write_i32(
+                base_addr.wrapping_add(offsets.discussion_time),
+                self.discussion_time,
+            ),
+            GameSettingsField::VotingTime => process.write_i32(
+                base_addr.wrapping_add(offsets.voting_time),
+                self.voting_time,
+            ),
+            GameSettingsField::ConfirmEject => process.write_u8(
+                base_addr.wrapping_add(offsets.confirm_eject),
+                self.confirm_eject as u8,
+            ),
+            GameSettingsField::VisualTasks => process.write_u8(
+                base_addr.wrapping_add(offsets.visual_tasks),
+                self.visual_tasks as u8,
+            ),
+            GameSettingsField::AnonymousVoting => process.write_u8(
+                base_addr.wrapping_add(offsets.anonymous_voting),
+                self.anonymous_voting as u8,
+            ),
+            GameSettingsField::TaskBarUpdates => process.write_i32(
+                base_addr.wrapping_add(offsets.task_bar_updates),
+                self.task_bar_updates,
+            ),
         }
-        process.write_i32(base_addr + offsets.kill_distance, self.kill_distance);
-        process.write_i32(base_addr + offsets.discussion_time, self.discussion_time);
-        process.write_i32(base_addr + offsets.voting_time, self.voting_time);
-        process.write_u8(base_addr + offsets.confirm_eject, self.confirm_eject as u8);
-        process.write_u8(base_addr + offsets.visual_tasks, self.visual_tasks as u8);
-        process.write_u8(
-            base_addr + offsets.anonymous_voting,
-            self.anonymous_voting as u8,
-        );
-        process.write_i32(base_addr + offsets.task_bar_updates, self.task_bar_updates);
     }
 }
 
@@ -147,21 +360,165 @@ pub struct AUProcessReadWrite<'a> {
 }
 
 impl<'a> AUProcessReadWrite<'a> {
+    /// Without Offsets.json, only the game settings are available, at the
+    /// offset found by signature scanning.
     pub fn new(
-        au_capture_offsets: &'a AUCaptureOffsets,
+        au_capture_offsets: Option<&AUCaptureOffsets>,
         au_process: &'a AUProcess,
     ) -> Option<Self> {
+        let dll_hash = au_process.dll_hash();
+        let game_options_offset = au_capture_offsets
+            .and_then(|x| x.game_options_offset(dll_hash))
+            .or_else(|| au_process.scanned_game_options_offset())?;
+        let mut offsets = Offsets::new(game_options_offset);
+        if let Some(au_capture_offsets) = au_capture_offsets {
+            offsets.game_state = GameStateOffsets::new(au_capture_offsets, dll_hash);
+            offsets.host = HostOffsets::new(au_capture_offsets, dll_hash);
+            offsets.lobby = LobbyOffsets::new(au_capture_offsets, dll_hash);
+        }
         Some(Self {
             au_process,
-            offsets: Offsets::new(au_capture_offsets.game_options_offset(&au_process.dll_hash())?),
+            offsets,
         })
     }
 
-    pub fn game_settings(&self) -> GameSettings {
+    pub fn game_state(&self) -> Option<GameState> {
+        let process = self.au_process.process();
+        let offsets = self.offsets.game_state.as_ref()?;
+        let game_assembly = process.base_addr_of_module_name("GameAssembly.dll")?;
+        let raw = process.read_i32(resolve_pointer_chain(
+            process,
+            game_assembly,
+            &offsets.game_state,
+        )?);
+        GameState::from_raw(raw, self.meeting_hud_state(game_assembly, offsets))
+    }
+
+    /// Whether the local client hosts the current lobby. `None` when the
+    /// offsets are unknown or the client is not in a lobby.
+    pub fn is_host(&self) -> Option<bool> {
+        let process = self.au_process.process();
+        let offsets = self.offsets.host.as_ref()?;
+        let game_assembly = process.base_addr_of_module_name("GameAssembly.dll")?;
+        let host_id = process.read_i32(resolve_pointer_chain(
+            process,
+            game_assembly,
+            &offsets.host_id,
+        )?);
+        let client_id = process.read_i32(resolve_pointer_chain(
+            process,
+            game_assembly,
+            &offsets.client_id,
+        )?);
+        if client_id < 0 {
+            return None;
+        }
+        Some(host_id == client_id)
+    }
+
+    pub fn lobby_info(&self) -> Option<LobbyInfo> {
+        let process = self.au_process.process();
+        let offsets = self.offsets.lobby.as_ref()?;
+        let game_assembly = process.base_addr_of_module_name("GameAssembly.dll")?;
+        let game_code = resolve_pointer_chain(process, game_assembly, &offsets.game_code)
+            .and_then(|x| read_string(process, process.read_u32(x), &offsets.string))
+            .and_then(|x| parse_game_code(&x));
+        let all_player_ptr = process.read_u32(resolve_pointer_chain(
+            process,
+            game_assembly,
+            &offsets.all_player_ptr,
+        )?);
+        let all_players = process.read_u32(resolve_pointer_chain(
+            process,
+            all_player_ptr,
+            &offsets.all_players,
+        )?);
+        let player_count = process.read_i32(resolve_pointer_chain(
+            process,
+            all_player_ptr,
+            &offsets.player_count,
+        )?);
+        if !(0..=MAX_PLAYERS).contains(&player_count) {
+            return None;
+        }
+        let players = (0..player_count as u32)
+            .filter_map(|i| {
+                let player_info = process.read_u32(
+                    all_players
+                        .wrapping_add(offsets.player_list_ptr)
+                        .wrapping_add(i.wrapping_mul(offsets.add_player_ptr)),
+                );
+                if player_info == 0 {
+                    return None;
+                }
+                Some(PlayerInfo {
+                    id: process.read_u8(player_info.wrapping_add(offsets.player_id)),
+                    name: read_string(
+                        process,
+                        process.read_u32(player_info.wrapping_add(offsets.player_name)),
+                        &offsets.string,
+                    )
+                    .unwrap_or_default(),
+                    color: process.read_u8(player_info.wrapping_add(offsets.color_id)),
+                })
+            })
+            .collect();
+        Some(LobbyInfo {
+            game_code,
+            player_count: player_count as usize,
+            players,
+        })
+    }
+
+    fn meeting_hud_state(&self, game_assembly: u32, offsets: &GameStateOffsets) -> Option<i32> {
+        let process = self.au_process.process();
+        let meeting_hud = process.read_u32(resolve_pointer_chain(
+            process,
+            game_assembly,
+            &offsets.meeting_hud,
+        )?);
+        if meeting_hud == 0 {
+            return None;
+        }
+        let cache_ptr = process.read_u32(resolve_pointer_chain(
+            process,
+            meeting_hud,
+            &offsets.meeting_hud_cache_ptr,
+        )?);
+        if cache_ptr == 0 {
+            return None;
+        }
+        Some(process.read_i32(resolve_pointer_chain(
+            process,
+            meeting_hud,
+            &offsets.meeting_hud_state,
+        )?))
+    }
+
+    pub fn pointer_chain(&self) -> Option<PointerChain> {
+        pointer_chain(self.au_process.process(), &self.offsets)
+    }
+
+    pub fn game_settings_layout(&self) -> Vec<(GameSettingsField, u32)> {
+        self.offsets.layout()
+    }
+
+    /// Reads `len` bytes from the `GameSettings` base address.
+    pub fn game_settings_bytes(&self, len: usize) -> Option<(u32, Vec<u8>)> {
+        let process = self.au_process.process();
+        let base_addr = base_addr(process, &self.offsets)?;
+        Some((base_addr, process.read_bytes(base_addr, len)))
+    }
+
+    pub fn game_settings(&self) -> Option<GameSettings> {
         GameSettings::read(self.au_process.process(), &self.offsets)
     }
 
-    pub fn set_game_settings(&self, value: GameSettings) {
-        value.write(self.au_process.process(), &self.offsets);
+    pub fn set_game_settings(
+        &self,
+        value: GameSettings,
+        fields: &[GameSettingsField],
+    ) -> Option<()> {
+        value.write(self.au_process.process(), &self.offsets, fields)
     }
 }
@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::{
+    app::{App, ApplyError},
+    enforce::EnforceAction,
+    game_settings::GameSettingsField,
+    history::HistoryQuery,
+    randomizer::RandomizerConstraints,
+    storage::PlayerCountRange,
+};
+
+/// A request from the UI or another instance, e.g.
+/// `{"type":"load_memory_from_file","payload":{"index":0}}`.
+#[derive(Debug, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum Command {
+    Init {},
+    OpenBrowser {
+        url: String,
+    },
+    SetGameSettingsName {
+        index: usize,
+        name: String,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        revision: Option<String>,
+    },
+    SetGameSettingsParent {
+        index: usize,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        parent: Option<String>,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        revision: Option<String>,
+    },
+    SetPlayerCountRange {
+        index: usize,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        range: Option<PlayerCountRange>,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        revision: Option<String>,
+    },
+    SetAutoApplyRecommended {
+        value: bool,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        revision: Option<String>,
+    },
+    SaveMemoryToFile {
+        index: usize,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        fields: Option<Vec<GameSettingsField>>,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        revision: Option<String>,
+    },
+    LoadMemoryFromFile {
+        index: usize,
+        #[serde(default)]
+        queue: bool,
+    },
+    CancelQueuedPreset {},
+    LoadBuiltinPreset {
+        #[serde(rename = "builtinIndex")]
+        builtin_index: usize,
+    },
+    CopyBuiltinPreset {
+        #[serde(rename = "builtinIndex")]
+        builtin_index: usize,
+        index: usize,
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        revision: Option<String>,
+    },
+    ApplyRandomGameSettings(RandomizerConstraints),
+    StartPlaylist {
+        indices: Vec<usize>,
+        #[serde(default)]
+        shuffle: bool,
+    },
+    StopPlaylist {},
+    StartEnforce {
+        index: usize,
+        action: EnforceAction,
+    },
+    StopEnforce {},
+    ClearEnforceLog {},
+    History(HistoryQuery),
+    ExportHistory {
+        #[serde(default)]
+        query: HistoryQuery,
+        path: String,
+    },
+    LobbyInfo {},
+    InspectMemory {},
+    ExportMemoryDump {
+        path: String,
+    },
+    ExportDiagnostics {
+        #[serde(default)]
+        #[cfg_attr(test, ts(optional))]
+        path: Option<String>,
+    },
+    /// Streams every `Event` on the instance socket.
+    Watch {},
+}
+
+impl Command {
+    pub fn parse(message: &str) -> Result<Self, ApplyError> {
+        serde_json::from_str(message).map_err(|err| ApplyError::InvalidCommand(err.to_string()))
+    }
+
+    /// Whether other processes may send this on the instance socket. Commands
+    /// that open a URL or write to a caller-chosen path are for the UI only.
+    pub fn is_allowed_on_instance_socket(&self) -> bool {
+        match self {
+            Command::OpenBrowser { .. }
+            | Command::ExportHistory { .. }
+            | Command::ExportMemoryDump { .. } => false,
+            Command::ExportDiagnostics { path } => path.is_none(),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub enum Response {
+    Ok(Value),
+    Err(ApplyError),
+}
+
+impl<T: Serialize> From<Result<T, ApplyError>> for Response {
+    fn from(result: Result<T, ApplyError>) -> Self {
+        match result
+            .and_then(|x| serde_json::to_value(x).map_err(|err| ApplyError::Error(err.to_string())))
+        {
+            Ok(value) => Response::Ok(value),
+            Err(err) => Response::Err(err),
+        }
+    }
+}
+
+/// Parses and runs `message`. Malformed input becomes an error response.
+pub async fn dispatch(app: &App, message: &str) -> Response {
+    match Command::parse(message) {
+        Err(err) => Response::Err(err),
+        Ok(command) => execute(app, command).await,
+    }
+}
+
+pub async fn execute(app: &App, command: Command) -> Response {
+    match command {
+        Command::Init {} => Ok(app.init().await).into(),
+        Command::OpenBrowser { url } => app.open_browser(&url).into(),
+        Command::SetGameSettingsName {
+            index,
+            name,
+            revision,
+        } => app
+            .set_game_settings_name(index, name, revision.as_deref())
+            .into(),
+        Command::SetGameSettingsParent {
+            index,
+            parent,
+            revision,
+        } => app
+            .set_game_settings_parent(index, parent, revision.as_deref())
+            .into(),
+        Command::SetPlayerCountRange {
+            index,
+            range,
+            revision,
+        } => app
+            .set_player_count_range(index, range, revision.as_deref())
+            .into(),
+        Command::SetAutoApplyRecommended { value, revision } => app
+            .set_auto_apply_recommended(value, revision.as_deref())
+            .into(),
+        Command::SaveMemoryToFile {
+            index,
+            fields,
+            revision,
+        } => app
+            .save_memory_to_file(index, fields, revision.as_deref())
+            .await
+            .into(),
+        Command::LoadMemoryFromFile { index, queue } => {
+            app.load_memory_from_file(index, queue).await.into()
+        }
+        Command::CancelQueuedPreset {} => {
+            app.cancel_queued_preset().await;
+            Ok(()).into()
+        }
+        Command::LoadBuiltinPreset { builtin_index } => {
+            app.load_builtin_preset(builtin_index).await.into()
+        }
+        Command::CopyBuiltinPreset {
+            builtin_index,
+            index,
+            revision,
+        } => app
+            .copy_builtin_preset(builtin_index, index, revision.as_deref())
+            .into(),
+        Command::ApplyRandomGameSettings(constraints) => {
+            app.apply_random_game_settings(&constraints).await.into()
+        }
+        Command::StartPlaylist { indices, shuffle } => {
+            app.start_playlist(indices, shuffle).await.into()
+        }
+        Command::StopPlaylist {} => {
+            app.stop_playlist().await;
+            Ok(()).into()
+        }
+        Command::StartEnforce { index, action } => app.start_enforce(index, action).await.into(),
+        Command::StopEnforce {} => {
+            app.stop_enforce().await;
+            Ok(()).into()
+        }
+        Command::ClearEnforceLog {} => {
+            app.clear_enforce_log().await;
+            Ok(()).into()
+        }
+        Command::History(query) => app.history(&query).into(),
+        Command::ExportHistory { query, path } => app.export_history(&query, &path).into(),
+        Command::LobbyInfo {} => Ok(app.lobby_info().await).into(),
+        Command::InspectMemory {} => app.inspect_memory().await.into(),
+        Command::ExportMemoryDump { path } => app.export_memory_dump(&path).await.into(),
+        Command::ExportDiagnostics { path } => app.export_diagnostics(path).await.into(),
+        Command::Watch {} => Response::Err(ApplyError::InvalidCommand(
+            "watch is only available on the instance socket".into(),
+        )),
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert!(matches!(
+        Command::parse(r#"{"type":"init","payload":{},"callback":"_1"}"#),
+        Ok(Command::Init {})
+    ));
+    assert!(matches!(
+        Command::parse(r#"{"type":"load_builtin_preset","payload":{"builtinIndex":2}}"#),
+        Ok(Command::LoadBuiltinPreset { builtin_index: 2 })
+    ));
+    assert!(matches!(
+        Command::parse(r#"{"type":"load_memory_from_file","payload":{"index":1}}"#),
+        Ok(Command::LoadMemoryFromFile {
+            index: 1,
+            queue: false
+        })
+    ));
+    for message in &[
+        "",
+        "{}",
+        r#"{"type":"unknown","payload":{}}"#,
+        r#"{"type":"set_game_settings_name","payload":{"index":-1,"name":"x"}}"#,
+    ] {
+        assert!(matches!(
+            Command::parse(message),
+            Err(ApplyError::InvalidCommand(_))
+        ));
+    }
+}
+
+#[test]
+fn test_is_allowed_on_instance_socket() {
+    let allowed = |message| {
+        Command::parse(message)
+            .unwrap()
+            .is_allowed_on_instance_socket()
+    };
+    assert!(allowed(
+        r#"{"type":"load_memory_from_file","payload":{"index":1}}"#
+    ));
+    assert!(allowed(r#"{"type":"export_diagnostics","payload":{}}"#));
+    assert!(!allowed(
+        r#"{"type":"open_browser","payload":{"url":"file:///"}}"#
+    ));
+    assert!(!allowed(
+        r#"{"type":"export_diagnostics","payload":{"path":"x.zip"}}"#
+    ));
+    assert!(!allowed(
+        r#"{"type":"export_memory_dump","payload":{"path":"x.json"}}"#
+    ));
+}
@@ -0,0 +1,187 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use directories_next::BaseDirs;
+use serde::Serialize;
+use zip::{write::FileOptions, ZipWriter};
+
+use super::{
+    app::ProcessStatus, auprocessreadwrite::PointerChain, clock::unix_time, paths::data_dir,
+    storage::Storage,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Module {
+    pub name: String,
+    pub base_addr: u32,
+}
+
+/// Keys of logged JSON values that may hold player, preset or lobby names.
+const PERSONAL_KEYS: [&str; 4] = ["name", "parent", "gameCode", "path"];
+
+/// Hides the user's home directory and account name.
+struct Scrubber {
+    replacements: Vec<(String, &'static str)>,
+}
+
+impl Scrubber {
+    fn new() -> Self {
+        let mut replacements = Vec::new();
+        if let Some(home) = BaseDirs::new().map(|x| x.home_dir().to_string_lossy().into_owned()) {
+            if !home.is_empty() {
+                // Logged JSON escapes backslashes.
+                replacements.push((home.replace('\\', "\\\\"), "~"));
+                replacements.push((home.clone(), "~"));
+            }
+            let user = Path::new(&home)
+                .file_name()
+                .map(|x| x.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // Shorter names would match ordinary words.
+            if user.len() >= 3 {
+                replacements.push((user, "<user>"));
+            }
+        }
+        Self { replacements }
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(text.to_owned(), |text, (from, to)| text.replace(from, to))
+    }
+}
+
+/// Replaces the string value of each `"key":` in `text`, which may be JSON
+/// embedded in a log line, with `"(hidden)"`.
+fn scrub_json_values(text: &str, keys: &[&str]) -> String {
+    let mut text = text.to_owned();
+    for key in keys {
+        let pattern = format!("\"{}\":", key);
+        let mut from = 0;
+        while let Some(i) = text[from..].find(&pattern) {
+            let start = from + i + pattern.len();
+            let value = text[start..].trim_start();
+            let start = text.len() - value.len();
+            from = start;
+            if !value.starts_with('"') {
+                continue;
+            }
+            let mut escaped = false;
+            let end = value[1..].find(|c| {
+                let quote = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                quote
+            });
+            let end = match end {
+                None => break,
+                Some(x) => start + 1 + x + 1,
+            };
+            text.replace_range(start..end, "\"(hidden)\"");
+            from = start + "\"(hidden)\"".len();
+        }
+    }
+    text
+}
+
+fn anonymized_status(mut status: ProcessStatus, scrubber: &Scrubber) -> ProcessStatus {
+    status.exe_path = status.exe_path.map(|x| scrubber.scrub(&x));
+    status.offsets_error = status.offsets_error.map(|x| scrubber.scrub(&x));
+    status.capture_error = status.capture_error.map(|x| scrubber.scrub(&x));
+    status.game_info.lobby = status.game_info.lobby.map(|x| x.anonymized());
+    status
+}
+
+/// Everything needed to tell why ausettings fails on someone else's machine.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+    pub app_version: &'static str,
+    pub os: String,
+    pub created_at: u64,
+    pub process_status: Option<ProcessStatus>,
+    pub modules: Vec<Module>,
+    pub pointer_chain: Option<PointerChain>,
+}
+
+impl Bundle {
+    pub fn new(
+        process_status: Option<ProcessStatus>,
+        modules: Vec<(String, u32)>,
+        pointer_chain: Option<PointerChain>,
+    ) -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION"),
+            os: format!("{} {}", env::consts::OS, env::consts::ARCH),
+            created_at: unix_time(),
+            process_status: process_status.map(|x| anonymized_status(x, &Scrubber::new())),
+            modules: modules
+                .into_iter()
+                .map(|(name, base_addr)| Module { name, base_addr })
+                .collect(),
+            pointer_chain,
+        }
+    }
+}
+
+pub fn default_path() -> PathBuf {
+    data_dir().join(format!("diagnostics-{}.zip", unix_time()))
+}
+
+/// Writes `diagnostics.json`, the anonymized `ausettings.json` and the log
+/// files, with names and the home directory hidden, into a zip at `path`.
+pub fn export(bundle: &Bundle, path: &Path) -> Result<()> {
+    let mut zip = ZipWriter::new(fs::File::create(path)?);
+    let options = FileOptions::default();
+    zip.start_file("diagnostics.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(bundle)?)?;
+    // A malformed file cannot be anonymized and is left out.
+    if let Ok(storage) = Storage::load() {
+        zip.start_file("ausettings.json", options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&storage.anonymized())?)?;
+    }
+    let scrubber = Scrubber::new();
+    if let Ok(entries) = fs::read_dir(data_dir().join("logs")) {
+        for entry in entries.filter_map(|x| x.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.ends_with(".log") {
+                continue;
+            }
+            let log = String::from_utf8_lossy(&fs::read(entry.path())?).into_owned();
+            let log = scrubber.scrub(&scrub_json_values(&log, &PERSONAL_KEYS));
+            zip.start_file(format!("logs/{}", name), options)?;
+            zip.write_all(log.as_bytes())?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+#[test]
+fn test_scrub() {
+    let log = r#"==> {"type":"set_game_settings_name","payload":{"index":0,"name":"bob \"b\""}}
+<-- window.onEvent({"players":[{"id":0,"name":"alice","color":1}],"gameCode": "ABCDEF","map":0})"#;
+    assert_eq!(
+        scrub_json_values(log, &PERSONAL_KEYS),
+        r#"==> {"type":"set_game_settings_name","payload":{"index":0,"name":"(hidden)"}}
+<-- window.onEvent({"players":[{"id":0,"name":"(hidden)","color":1}],"gameCode": "(hidden)","map":0})"#
+    );
+    let scrubber = Scrubber {
+        replacements: vec![
+            (r"C:\\Users\\alice".into(), "~"),
+            (r"C:\Users\alice".into(), "~"),
+            ("alice".into(), "<user>"),
+        ],
+    };
+    assert_eq!(
+        scrubber.scrub(
+            r#"Hashing C:\Users\alice\GameAssembly.dll {"exePath":"C:\\Users\\alice\\x"} D:\alice"#
+        ),
+        r#"Hashing ~\GameAssembly.dll {"exePath":"~\\x"} D:\<user>"#
+    );
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::{
+    clock::unix_time,
+    game_settings::{GameSettings, GameSettingsField},
+};
+
+/// Older drift events are dropped once the log grows past this.
+const MAX_LOG_LEN: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub enum EnforceAction {
+    Reapply,
+    Alert,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct Enforcement {
+    pub preset: usize,
+    pub action: EnforceAction,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct DriftEvent {
+    /// Seconds since the Unix epoch.
+    #[cfg_attr(test, ts(type = "number"))]
+    pub timestamp: u64,
+    pub field: GameSettingsField,
+    pub expected: f32,
+    pub actual: f32,
+    pub action: EnforceAction,
+}
+
+/// Returns the controllable `fields` whose live value differs from the
+/// locked one.
+pub fn drifted_fields(
+    expected: &GameSettings,
+    actual: &GameSettings,
+    fields: &[GameSettingsField],
+) -> Vec<GameSettingsField> {
+    fields
+        .iter()
+        .copied()
+        .filter(|x| x.is_controllable())
+        .filter(|x| (expected.value(*x) - actual.value(*x)).abs() > 0.001)
+        .collect()
+}
+
+pub fn record_drift(
+    log: &mut Vec<DriftEvent>,
+    expected: &GameSettings,
+    actual: &GameSettings,
+    fields: &[GameSettingsField],
+    action: EnforceAction,
+) {
+    let timestamp = unix_time();
+    log.extend(fields.iter().map(|x| DriftEvent {
+        timestamp,
+        field: *x,
+        expected: expected.value(*x),
+        actual: actual.value(*x),
+        action,
+    }));
+    if log.len() > MAX_LOG_LEN {
+        log.drain(0..log.len() - MAX_LOG_LEN);
+    }
+}
+
+#[test]
+fn test_drifted_fields() {
+    let expected = GameSettings {
+        map: 1,
+        player_speed: 1.25,
+        voting_time: 120,
+        ..Default::default()
+    };
+    let actual = GameSettings {
+        player_speed: 1.25,
+        voting_time: 60,
+        ..Default::default()
+    };
+    assert_eq!(
+        drifted_fields(&expected, &actual, &GameSettingsField::ALL),
+        vec![GameSettingsField::VotingTime]
+    );
+    assert!(drifted_fields(&expected, &actual, &[GameSettingsField::PlayerSpeed]).is_empty());
+
+    let mut log = Vec::new();
+    for _ in 0..MAX_LOG_LEN + 1 {
+        record_drift(
+            &mut log,
+            &expected,
+            &actual,
+            &[GameSettingsField::VotingTime],
+            EnforceAction::Alert,
+        );
+    }
+    assert_eq!(log.len(), MAX_LOG_LEN);
+}
@@ -0,0 +1,41 @@
+use serde::Serialize;
+use tokio::sync::broadcast::Sender;
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::{
+    app::ProcessStatus, game_settings::GameSettingsField, game_state::GameInfo,
+    storage::GameSettingsListItem,
+};
+
+/// Pushed from core to every subscriber: the web UI and `--watch` clients.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(tag = "type", content = "payload", rename_all = "camelCase")]
+pub enum Event {
+    ProcessAttached,
+    ProcessDetached,
+    OffsetsLoaded,
+    OffsetsFailed(String),
+    GameStateChanged(GameInfo),
+    /// ausettings wrote these fields to the game.
+    SettingsApplied(Vec<GameSettingsField>),
+    /// These fields no longer match the enforced preset.
+    SettingsDrifted(Vec<GameSettingsField>),
+    /// ausettings.json changed. `revision` is to be passed back with edits.
+    #[serde(rename_all = "camelCase")]
+    StorageChanged {
+        game_settings_list: Vec<GameSettingsListItem>,
+        revision: Option<String>,
+    },
+    /// The queued preset, the enforcement or its log changed.
+    AppStateChanged,
+    /// Sent after every other event.
+    ProcessStatusChanged(Box<ProcessStatus>),
+    Error(String),
+}
+
+/// Sends `event` whether or not anyone is subscribed.
+pub fn emit(events: &Sender<Event>, event: Event) {
+    let _ = events.send(event);
+}
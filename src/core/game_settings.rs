@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use ts_rs::TS;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
 #[serde(rename_all = "camelCase")]
 pub struct GameSettings {
     #[serde(skip)]
@@ -24,3 +27,165 @@ pub struct GameSettings {
     pub anonymous_voting: bool,
     pub task_bar_updates: i32,
 }
+
+impl GameSettings {
+    pub fn value(&self, field: GameSettingsField) -> f32 {
+        match field {
+            GameSettingsField::Map => self.map as f32,
+            GameSettingsField::PlayerSpeed => self.player_speed,
+            GameSettingsField::CrewmateVision => self.crewmate_vision,
+            GameSettingsField::ImpostorVision => self.impostor_vision,
+            GameSettingsField::KillCooldown => self.kill_cooldown,
+            GameSettingsField::CommonTasks => self.common_tasks as f32,
+            GameSettingsField::LongTasks => self.long_tasks as f32,
+            GameSettingsField::ShortTasks => self.short_tasks as f32,
+            GameSettingsField::EmergencyMeeting => self.emergency_meeting as f32,
+            GameSettingsField::EmergencyCooldown => self.emergency_cooldown as f32,
+            GameSettingsField::Impostors => self.impostors as f32,
+            GameSettingsField::KillDistance => self.kill_distance as f32,
+            GameSettingsField::DiscussionTime => self.discussion_time as f32,
+            GameSettingsField::VotingTime => self.voting_time as f32,
+            GameSettingsField::ConfirmEject => self.confirm_eject as i32 as f32,
+            GameSettingsField::VisualTasks => self.visual_tasks as i32 as f32,
+            GameSettingsField::AnonymousVoting => self.anonymous_voting as i32 as f32,
+            GameSettingsField::TaskBarUpdates => self.task_bar_updates as f32,
+        }
+    }
+
+    pub fn set_value(&mut self, field: GameSettingsField, value: f32) {
+        match field {
+            GameSettingsField::Map => self.map = value as i32,
+            GameSettingsField::PlayerSpeed => self.player_speed = value,
+            GameSettingsField::CrewmateVision => self.crewmate_vision = value,
+            GameSettingsField::ImpostorVision => self.impostor_vision = value,
+            GameSettingsField::KillCooldown => self.kill_cooldown = value,
+            GameSettingsField::CommonTasks => self.common_tasks = value as i32,
+            GameSettingsField::LongTasks => self.long_tasks = value as i32,
+            GameSettingsField::ShortTasks => self.short_tasks = value as i32,
+            GameSettingsField::EmergencyMeeting => self.emergency_meeting = value as i32,
+            GameSettingsField::EmergencyCooldown => self.emergency_cooldown = value as i32,
+            GameSettingsField::Impostors => self.impostors = value as i32,
+            GameSettingsField::KillDistance => self.kill_distance = value as i32,
+            GameSettingsField::DiscussionTime => self.discussion_time = value as i32,
+            GameSettingsField::VotingTime => self.voting_time = value as i32,
+            GameSettingsField::ConfirmEject => self.confirm_eject = value != 0.0,
+            GameSettingsField::VisualTasks => self.visual_tasks = value != 0.0,
+            GameSettingsField::AnonymousVoting => self.anonymous_voting = value != 0.0,
+            GameSettingsField::TaskBarUpdates => self.task_bar_updates = value as i32,
+        }
+    }
+
+    /// Returns the fields whose value the game would not accept.
+    pub fn invalid_fields(&self) -> Vec<GameSettingsField> {
+        GameSettingsField::ALL
+            .iter()
+            .copied()
+            .filter(|x| !x.range().contains(self.value(*x)))
+            .collect()
+    }
+
+    /// Overwrites `fields` of `self` with the values in `other`.
+    pub fn merge(&mut self, other: &GameSettings, fields: &[GameSettingsField]) {
+        for field in fields {
+            self.set_value(*field, other.value(*field));
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub enum GameSettingsField {
+    Map,
+    PlayerSpeed,
+    CrewmateVision,
+    ImpostorVision,
+    KillCooldown,
+    CommonTasks,
+    LongTasks,
+    ShortTasks,
+    EmergencyMeeting,
+    EmergencyCooldown,
+    Impostors,
+    KillDistance,
+    DiscussionTime,
+    VotingTime,
+    ConfirmEject,
+    VisualTasks,
+    AnonymousVoting,
+    TaskBarUpdates,
+}
+
+/// Legal values of a field: `min`, `min + step`, ... up to `max`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldRange {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl FieldRange {
+    const fn new(min: f32, max: f32, step: f32) -> Self {
+        Self { min, max, step }
+    }
+
+    pub fn contains(&self, value: f32) -> bool {
+        if value < self.min || self.max < value {
+            return false;
+        }
+        let steps = (value - self.min) / self.step;
+        (steps - steps.round()).abs() < 0.001
+    }
+}
+
+impl GameSettingsField {
+    pub const ALL: [GameSettingsField; 18] = [
+        GameSettingsField::Map,
+        GameSettingsField::PlayerSpeed,
+        GameSettingsField::CrewmateVision,
+        GameSettingsField::ImpostorVision,
+        GameSettingsField::KillCooldown,
+        GameSettingsField::CommonTasks,
+        GameSettingsField::LongTasks,
+        GameSettingsField::ShortTasks,
+        GameSettingsField::EmergencyMeeting,
+        GameSettingsField::EmergencyCooldown,
+        GameSettingsField::Impostors,
+        GameSettingsField::KillDistance,
+        GameSettingsField::DiscussionTime,
+        GameSettingsField::VotingTime,
+        GameSettingsField::ConfirmEject,
+        GameSettingsField::VisualTasks,
+        GameSettingsField::AnonymousVoting,
+        GameSettingsField::TaskBarUpdates,
+    ];
+
+    /// Whether ausettings writes the field back to the game. The map and the
+    /// number of impostors are left to the lobby menu.
+    pub fn is_controllable(self) -> bool {
+        self != GameSettingsField::Map && self != GameSettingsField::Impostors
+    }
+
+    pub fn range(self) -> FieldRange {
+        match self {
+            GameSettingsField::Map => FieldRange::new(0.0, 2.0, 1.0),
+            GameSettingsField::PlayerSpeed => FieldRange::new(0.5, 3.0, 0.25),
+            GameSettingsField::CrewmateVision => FieldRange::new(0.25, 5.0, 0.25),
+            GameSettingsField::ImpostorVision => FieldRange::new(0.25, 5.0, 0.25),
+            GameSettingsField::KillCooldown => FieldRange::new(10.0, 60.0, 2.5),
+            GameSettingsField::CommonTasks => FieldRange::new(0.0, 2.0, 1.0),
+            GameSettingsField::LongTasks => FieldRange::new(0.0, 3.0, 1.0),
+            GameSettingsField::ShortTasks => FieldRange::new(0.0, 5.0, 1.0),
+            GameSettingsField::EmergencyMeeting => FieldRange::new(0.0, 9.0, 1.0),
+            GameSettingsField::EmergencyCooldown => FieldRange::new(0.0, 60.0, 5.0),
+            GameSettingsField::Impostors => FieldRange::new(1.0, 3.0, 1.0),
+            GameSettingsField::KillDistance => FieldRange::new(0.0, 2.0, 1.0),
+            GameSettingsField::DiscussionTime => FieldRange::new(0.0, 120.0, 15.0),
+            GameSettingsField::VotingTime => FieldRange::new(0.0, 300.0, 15.0),
+            GameSettingsField::ConfirmEject
+            | GameSettingsField::VisualTasks
+            | GameSettingsField::AnonymousVoting => FieldRange::new(0.0, 1.0, 1.0),
+            GameSettingsField::TaskBarUpdates => FieldRange::new(0.0, 2.0, 1.0),
+        }
+    }
+}
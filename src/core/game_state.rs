@@ -0,0 +1,56 @@
+use serde::Serialize;
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::lobby::LobbyInfo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub enum GameState {
+    Menu,
+    Lobby,
+    InGame,
+    Meeting,
+}
+
+impl GameState {
+    /// Maps `InnerNetClient.GameStates` (NotJoined, Joined, Started, Ended)
+    /// and `MeetingHud.VoteStates`, where anything below 4 (Proceeding) means
+    /// a meeting is open.
+    pub fn from_raw(raw: i32, meeting_hud_state: Option<i32>) -> Option<Self> {
+        match raw {
+            0 => Some(GameState::Menu),
+            1 | 3 => Some(GameState::Lobby),
+            2 => match meeting_hud_state {
+                Some(x) if x < 4 => Some(GameState::Meeting),
+                _ => Some(GameState::InGame),
+            },
+            _ => None,
+        }
+    }
+
+    pub fn is_playing(self) -> bool {
+        self == GameState::InGame || self == GameState::Meeting
+    }
+}
+
+/// Everything the game state watcher reads from the process on each tick.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct GameInfo {
+    pub game_state: Option<GameState>,
+    pub is_host: Option<bool>,
+    pub lobby: Option<LobbyInfo>,
+}
+
+#[test]
+fn test_from_raw() {
+    assert_eq!(GameState::from_raw(0, None), Some(GameState::Menu));
+    assert_eq!(GameState::from_raw(3, None), Some(GameState::Lobby));
+    assert_eq!(GameState::from_raw(2, None), Some(GameState::InGame));
+    assert_eq!(GameState::from_raw(2, Some(4)), Some(GameState::InGame));
+    assert_eq!(GameState::from_raw(2, Some(1)), Some(GameState::Meeting));
+    assert_eq!(GameState::from_raw(-1, None), None);
+}
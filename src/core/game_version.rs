@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::aucaptureoffsets::AUCaptureOffsets;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub enum Storefront {
+    Steam,
+    Epic,
+    Itch,
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct GameVersion {
+    /// e.g. `2021.3.5`
+    pub version: String,
+    pub storefront: Storefront,
+}
+
+/// Known GameAssembly.dll hashes, keyed like the offsets data. Entries here
+/// take precedence over the offsets' descriptions.
+static GAME_VERSIONS: Lazy<HashMap<String, GameVersion>> =
+    Lazy::new(|| serde_json::from_str(include_str!("game_versions.json")).unwrap());
+
+/// Names the game version of the GameAssembly.dll with this hash.
+pub fn identify(sha256: &str, offsets: Option<&AUCaptureOffsets>) -> Option<GameVersion> {
+    if let Some(game_version) = GAME_VERSIONS.get(sha256) {
+        return Some(game_version.clone());
+    }
+    let offsets = offsets?;
+    Some(from_description(
+        offsets.description(sha256)?,
+        offsets.is_epic(sha256),
+    ))
+}
+
+/// Parses an offsets description such as `v2021.3.5s`. The trailing letter
+/// of the version is the storefront: s(team), e(pic) or i(tch).
+fn from_description(description: &str, is_epic: Option<bool>) -> GameVersion {
+    let token = description
+        .split_whitespace()
+        .map(|x| x.trim_start_matches('v'))
+        .find(|x| x.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(description);
+    let version = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let storefront = match (is_epic, &token[version.len()..]) {
+        (Some(true), _) | (_, "e") => Storefront::Epic,
+        (_, "s") => Storefront::Steam,
+        (_, "i") => Storefront::Itch,
+        _ => Storefront::Unknown,
+    };
+    GameVersion {
+        version: version.into(),
+        storefront,
+    }
+}
+
+#[test]
+fn test_from_description() {
+    let parse = |description, is_epic| {
+        let x = from_description(description, is_epic);
+        (x.version, x.storefront)
+    };
+    assert_eq!(
+        parse("v2021.3.5s", None),
+        ("2021.3.5".into(), Storefront::Steam)
+    );
+    assert_eq!(
+        parse("2021.4.12e", Some(true)),
+        ("2021.4.12".into(), Storefront::Epic)
+    );
+    assert_eq!(
+        parse("Itch v2020.12.9i", None),
+        ("2020.12.9".into(), Storefront::Itch)
+    );
+    assert_eq!(
+        parse("v2021.5.10", Some(true)),
+        ("2021.5.10".into(), Storefront::Epic)
+    );
+    assert_eq!(
+        parse("v2021.5.10", None),
+        ("2021.5.10".into(), Storefront::Unknown)
+    );
+}
+
+#[test]
+fn test_game_versions() {
+    // Keys must match `AUProcess::dll_hash`, which is upper-case hex.
+    assert!(GAME_VERSIONS
+        .keys()
+        .all(|x| { x.len() == 64 && x.chars().all(|c| matches!(c, '0'..='9' | 'A'..='F')) }));
+    assert!(GAME_VERSIONS.values().all(|x| !x.version.is_empty()));
+}
+
+#[test]
+fn test_identify_bundled() {
+    for (sha256, game_version) in GAME_VERSIONS.iter() {
+        assert_eq!(identify(sha256, None).as_ref(), Some(game_version));
+    }
+}
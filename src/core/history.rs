@@ -0,0 +1,134 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::{game_settings::GameSettings, paths::data_dir};
+
+fn history_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRecord {
+    /// Seconds since the Unix epoch.
+    #[cfg_attr(test, ts(type = "number"))]
+    pub timestamp: u64,
+    pub game_code: Option<String>,
+    pub player_count: Option<usize>,
+    /// `GameSettings` does not serialize these two, so they are kept here.
+    pub map: i32,
+    pub impostors: i32,
+    pub game_settings: GameSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    #[cfg_attr(test, ts(type = "number", optional))]
+    pub since: Option<u64>,
+    #[cfg_attr(test, ts(type = "number", optional))]
+    pub until: Option<u64>,
+    #[cfg_attr(test, ts(optional))]
+    pub game_code: Option<String>,
+    /// Keeps only the newest `limit` records.
+    #[cfg_attr(test, ts(optional))]
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, record: &MatchRecord) -> bool {
+        self.since.is_none_or(|x| x <= record.timestamp)
+            && self.until.is_none_or(|x| record.timestamp <= x)
+            && self
+                .game_code
+                .as_ref()
+                .is_none_or(|x| record.game_code.as_ref() == Some(x))
+    }
+
+    fn apply(&self, lines: &str) -> Vec<MatchRecord> {
+        let mut records: Vec<MatchRecord> = lines
+            .lines()
+            .filter_map(|x| serde_json::from_str(x).ok())
+            .filter(|x| self.matches(x))
+            .collect();
+        if let Some(limit) = self.limit {
+            records.drain(0..records.len().saturating_sub(limit));
+        }
+        records
+    }
+}
+
+pub fn append(record: &MatchRecord) -> Result<()> {
+    let path = history_path();
+    fs::create_dir_all(path.parent().unwrap())?;
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+pub fn query(query: &HistoryQuery) -> Result<Vec<MatchRecord>> {
+    let lines = match fs::read_to_string(history_path()) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        x => x?,
+    };
+    Ok(query.apply(&lines))
+}
+
+/// Writes the matching records to `path` as a JSON array and returns how
+/// many were written.
+pub fn export(query: &HistoryQuery, path: &Path) -> Result<usize> {
+    let records = self::query(query)?;
+    fs::write(path, serde_json::to_string_pretty(&records)?)?;
+    Ok(records.len())
+}
+
+#[test]
+fn test_query() {
+    let record = |timestamp, game_code: &str| {
+        serde_json::to_string(&MatchRecord {
+            timestamp,
+            game_code: Some(game_code.into()),
+            player_count: Some(10),
+            map: 0,
+            impostors: 2,
+            game_settings: Default::default(),
+        })
+        .unwrap()
+    };
+    let lines = [
+        record(100, "AAAAAA"),
+        "broken line".into(),
+        record(200, "BBBBBB"),
+        record(300, "AAAAAA"),
+    ]
+    .join("\n");
+    let timestamps = |query: HistoryQuery| -> Vec<u64> {
+        query.apply(&lines).iter().map(|x| x.timestamp).collect()
+    };
+    assert_eq!(timestamps(Default::default()), vec![100, 200, 300]);
+    assert_eq!(
+        timestamps(HistoryQuery {
+            since: Some(150),
+            ..Default::default()
+        }),
+        vec![200, 300]
+    );
+    assert_eq!(
+        timestamps(HistoryQuery {
+            game_code: Some("AAAAAA".into()),
+            limit: Some(1),
+            ..Default::default()
+        }),
+        vec![300]
+    );
+}
@@ -0,0 +1,147 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use serde::Serialize;
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::game_settings::GameSettingsField;
+
+/// Bytes read from the `GameSettings` base address. The known fields end at
+/// 0x54, so this leaves room for fields a game update may add.
+pub const REGION_SIZE: usize = 0x80;
+const ROW_SIZE: usize = 4;
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub base_addr: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// One 4-byte word of the region, read as every type a field may have.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryRow {
+    pub offset: u32,
+    pub bytes: Vec<u8>,
+    pub as_i32: i32,
+    /// `null` for NaN and infinities.
+    #[cfg_attr(test, ts(type = "number | null"))]
+    pub as_f32: f32,
+    pub as_bool: bool,
+    /// Per byte, whether it differs from the previous snapshot.
+    pub changed: Vec<bool>,
+    /// Known fields that start within this word.
+    pub fields: Vec<GameSettingsField>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutEntry {
+    pub field: GameSettingsField,
+    pub offset: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDump {
+    pub base_addr: u32,
+    pub layout: Vec<LayoutEntry>,
+    pub rows: Vec<MemoryRow>,
+}
+
+/// Splits `snapshot` into rows. Bytes are compared with `previous` only when
+/// both were read from the same address.
+pub fn dump(
+    snapshot: &Snapshot,
+    previous: Option<&Snapshot>,
+    layout: &[(GameSettingsField, u32)],
+) -> MemoryDump {
+    let previous = previous.filter(|x| x.base_addr == snapshot.base_addr);
+    let rows = snapshot
+        .bytes
+        .chunks_exact(ROW_SIZE)
+        .enumerate()
+        .map(|(i, bytes)| {
+            let offset = (i * ROW_SIZE) as u32;
+            let word = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            MemoryRow {
+                offset,
+                bytes: bytes.to_vec(),
+                as_i32: i32::from_le_bytes(word),
+                as_f32: f32::from_le_bytes(word),
+                as_bool: bytes[0] != 0,
+                changed: (0..ROW_SIZE)
+                    .map(|j| {
+                        previous
+                            .and_then(|x| x.bytes.get(i * ROW_SIZE + j))
+                            .is_some_and(|x| *x != bytes[j])
+                    })
+                    .collect(),
+                fields: layout
+                    .iter()
+                    .filter(|(_, x)| (offset..offset + ROW_SIZE as u32).contains(x))
+                    .map(|(field, _)| *field)
+                    .collect(),
+            }
+        })
+        .collect();
+    MemoryDump {
+        base_addr: snapshot.base_addr,
+        layout: layout
+            .iter()
+            .map(|(field, offset)| LayoutEntry {
+                field: *field,
+                offset: *offset,
+            })
+            .collect(),
+        rows,
+    }
+}
+
+pub fn export(dump: &MemoryDump, path: &Path) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(dump)?)?;
+    Ok(())
+}
+
+#[test]
+fn test_dump() {
+    let layout = [
+        (GameSettingsField::PlayerSpeed, 0x4),
+        (GameSettingsField::ConfirmEject, 0x8),
+        (GameSettingsField::VisualTasks, 0x9),
+    ];
+    let previous = Snapshot {
+        base_addr: 0x1000,
+        bytes: [[2, 0, 0, 0], 1.5f32.to_le_bytes(), [1, 0, 0, 0]].concat(),
+    };
+    let snapshot = Snapshot {
+        bytes: [[2, 0, 0, 0], 2.0f32.to_le_bytes(), [1, 1, 0, 0]].concat(),
+        ..previous.clone()
+    };
+    let result = dump(&snapshot, Some(&previous), &layout);
+    assert_eq!(result.rows.len(), 3);
+    assert_eq!(result.rows[0].as_i32, 2);
+    assert_eq!(result.rows[0].changed, [false; 4]);
+    assert_eq!(result.rows[1].as_f32, 2.0);
+    assert_eq!(result.rows[1].fields, [GameSettingsField::PlayerSpeed]);
+    assert_eq!(result.rows[1].changed, [false, false, true, true]);
+    assert_eq!(
+        result.rows[2].fields,
+        [
+            GameSettingsField::ConfirmEject,
+            GameSettingsField::VisualTasks
+        ]
+    );
+    assert_eq!(result.rows[2].changed, [false, true, false, false]);
+
+    let moved = Snapshot {
+        base_addr: 0x2000,
+        ..snapshot.clone()
+    };
+    let result = dump(&moved, Some(&previous), &layout);
+    assert!(result.rows.iter().all(|x| x.changed == [false; 4]));
+}
@@ -0,0 +1,148 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::Path,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use fs2::FileExt;
+
+use super::paths::{data_dir, write_atomically};
+
+/// How long a secondary instance waits for the primary to publish its port.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Held by the running instance for its whole lifetime.
+pub struct InstanceLock(File);
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+/// Where the primary instance listens, as published in `instance.port`: the
+/// port on the first line and the token on the second.
+pub struct Endpoint {
+    pub port: u16,
+    /// Secret that a connection has to send as its first line. Only the user
+    /// who can read the data directory learns it.
+    pub token: String,
+}
+
+impl Endpoint {
+    fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        let port = lines.next()?.trim().parse().ok()?;
+        let token = lines.next()?.trim().to_owned();
+        if token.is_empty() {
+            return None;
+        }
+        Some(Self { port, token })
+    }
+
+    fn connect(&self) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port))?;
+        writeln!(stream, "{}", self.token)?;
+        Ok(stream)
+    }
+}
+
+pub enum Instance {
+    /// This is the only instance. Commands from others arrive on the listener
+    /// as JSON lines after the token.
+    Primary(InstanceLock, TcpListener, String),
+    /// Another instance is running and listens here.
+    Secondary(Endpoint),
+    /// Another instance holds the lock but has not published a reachable
+    /// port within `STARTUP_TIMEOUT`.
+    Unresponsive,
+}
+
+/// Waits until the primary instance has published its port and accepts
+/// connections on it.
+fn wait_for_primary(port_path: &Path) -> Option<Endpoint> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        let endpoint = fs::read_to_string(port_path)
+            .ok()
+            .and_then(|x| Endpoint::parse(&x));
+        if let Some(endpoint) = endpoint {
+            if TcpStream::connect((Ipv4Addr::LOCALHOST, endpoint.port)).is_ok() {
+                return Some(endpoint);
+            }
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        sleep(Duration::from_millis(100));
+    }
+}
+
+pub fn acquire() -> Result<Instance> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(dir.join("instance.lock"))?;
+    let port_path = dir.join("instance.port");
+    match file.try_lock_exclusive() {
+        // Compare OS error codes: on Windows every lock error has the same
+        // uncategorized kind.
+        Err(err) if err.raw_os_error() == fs2::lock_contended_error().raw_os_error() => {
+            return Ok(match wait_for_primary(&port_path) {
+                Some(endpoint) => Instance::Secondary(endpoint),
+                None => Instance::Unresponsive,
+            });
+        }
+        x => x?,
+    }
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    write_atomically(&port_path, &format!("{}\n{}\n", port, token))?;
+    Ok(Instance::Primary(InstanceLock(file), listener, token))
+}
+
+/// Sends each command to the running instance and returns its replies.
+pub fn forward(endpoint: &Endpoint, commands: &[String]) -> Result<Vec<String>> {
+    let mut stream = endpoint.connect()?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    commands
+        .iter()
+        .map(|command| {
+            writeln!(stream, "{}", command)?;
+            let mut reply = String::new();
+            if reader.read_line(&mut reply)? == 0 {
+                bail!("connection closed");
+            }
+            Ok(reply.trim_end().to_owned())
+        })
+        .collect()
+}
+
+/// Subscribes to the running instance's events and passes each JSON line to
+/// `f` until the connection closes.
+pub fn watch(endpoint: &Endpoint, mut f: impl FnMut(&str)) -> Result<()> {
+    let mut stream = endpoint.connect()?;
+    writeln!(stream, r#"{{"type":"watch","payload":{{}}}}"#)?;
+    for line in BufReader::new(stream).lines() {
+        f(&line?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_endpoint_parse() {
+    let endpoint = Endpoint::parse("51234\nABCDEF\n").unwrap();
+    assert_eq!(endpoint.port, 51234);
+    assert_eq!(endpoint.token, "ABCDEF");
+    assert!(Endpoint::parse("51234\n").is_none());
+    assert!(Endpoint::parse("51234").is_none());
+    assert!(Endpoint::parse("").is_none());
+}
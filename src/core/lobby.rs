@@ -0,0 +1,49 @@
+use serde::Serialize;
+#[cfg(test)]
+use ts_rs::TS;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerInfo {
+    pub id: u8,
+    pub name: String,
+    pub color: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct LobbyInfo {
+    pub game_code: Option<String>,
+    pub player_count: usize,
+    pub players: Vec<PlayerInfo>,
+}
+
+impl LobbyInfo {
+    /// Hides the game code and replaces player names with `Player N`.
+    pub fn anonymized(mut self) -> Self {
+        self.game_code = self.game_code.map(|_| "(hidden)".into());
+        for (i, player) in self.players.iter_mut().enumerate() {
+            player.name = format!("Player {}", i + 1);
+        }
+        self
+    }
+}
+
+/// The lobby code text reads e.g. "CODE\r\nABCDEF"; only the last line is
+/// the code itself.
+pub fn parse_game_code(text: &str) -> Option<String> {
+    let code = text.lines().last()?.trim();
+    if code.is_empty() {
+        return None;
+    }
+    Some(code.into())
+}
+
+#[test]
+fn test_parse_game_code() {
+    assert_eq!(parse_game_code("CODE\r\nABCDEF"), Some("ABCDEF".into()));
+    assert_eq!(parse_game_code("QWERTY"), Some("QWERTY".into()));
+    assert_eq!(parse_game_code(""), None);
+}
@@ -0,0 +1,50 @@
+use std::env;
+
+use flexi_logger::{
+    detailed_format, Cleanup, Criterion, Duplicate, FileSpec, Logger, LoggerHandle, Naming,
+};
+
+use super::paths::{config_log_level, data_dir};
+
+pub const LOG_ENV: &str = "AUSETTINGS_LOG";
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Chooses the log specification, e.g. `debug` or `info,ausettings::ui=trace`.
+fn log_level(command_line: Option<String>) -> String {
+    command_line
+        .or_else(|| env::var(LOG_ENV).ok().filter(|x| !x.is_empty()))
+        .or_else(config_log_level)
+        .unwrap_or_else(|| DEFAULT_LOG_LEVEL.into())
+}
+
+/// Writes the log to `logs/ausettings.log` in the data directory, keeping a
+/// few rotated files. Debug builds also log to stderr. The returned handle
+/// must be kept alive.
+pub fn init_logging(command_line: Option<String>) -> Option<LoggerHandle> {
+    let spec = log_level(command_line);
+    let logger = Logger::try_with_str(&spec).unwrap_or_else(|err| {
+        eprintln!("Error: invalid log level {:?}. {}", spec, err);
+        Logger::try_with_str(DEFAULT_LOG_LEVEL).unwrap()
+    });
+    logger
+        .log_to_file(
+            FileSpec::default()
+                .directory(data_dir().join("logs"))
+                .basename("ausettings")
+                .suppress_timestamp(),
+        )
+        .rotate(
+            Criterion::Size(1024 * 1024),
+            Naming::Numbers,
+            Cleanup::KeepLogFiles(4),
+        )
+        .format(detailed_format)
+        .duplicate_to_stderr(if cfg!(debug_assertions) {
+            Duplicate::All
+        } else {
+            Duplicate::None
+        })
+        .start()
+        .map_err(|err| eprintln!("Error: logging is unavailable. {}", err))
+        .ok()
+}
@@ -0,0 +1,153 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use directories_next::ProjectDirs;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+pub const DATA_DIR_ENV: &str = "AUSETTINGS_DATA_DIR";
+/// A file with this name next to the executable keeps all data beside it.
+pub const PORTABLE_MARKER: &str = "ausettings.portable";
+
+static DATA_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Contents of `config.json` in the config directory.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Config {
+    /// Relative paths are resolved against the config directory.
+    data_dir: Option<PathBuf>,
+    log_level: Option<String>,
+    #[serde(default)]
+    debug: bool,
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("net", "prgrssv", "ausettings")
+}
+
+fn read_config(project_dirs: &ProjectDirs) -> Option<Config> {
+    let config_str = fs::read_to_string(project_dirs.config_dir().join("config.json")).ok()?;
+    serde_json::from_str(&config_str).ok()
+}
+
+/// Candidate data directories, in order of precedence.
+#[derive(Default)]
+struct DataDirSources {
+    command_line: Option<PathBuf>,
+    env: Option<PathBuf>,
+    portable: Option<PathBuf>,
+    config: Option<PathBuf>,
+    default: PathBuf,
+}
+
+impl DataDirSources {
+    fn read(command_line: Option<PathBuf>) -> Self {
+        let project_dirs = project_dirs();
+        let config = project_dirs
+            .as_ref()
+            .and_then(|x| Some(x.config_dir().join(read_config(x)?.data_dir?)));
+        let portable = env::current_exe()
+            .ok()
+            .and_then(|x| x.parent().map(|x| x.to_owned()))
+            .filter(|x| x.join(PORTABLE_MARKER).exists());
+        Self {
+            command_line,
+            env: env::var_os(DATA_DIR_ENV)
+                .filter(|x| !x.is_empty())
+                .map(PathBuf::from),
+            portable,
+            config,
+            default: match project_dirs {
+                Some(x) => x.data_dir().into(),
+                None => env::current_dir().unwrap_or_default(),
+            },
+        }
+    }
+
+    fn resolve(self) -> PathBuf {
+        self.command_line
+            .or(self.env)
+            .or(self.portable)
+            .or(self.config)
+            .unwrap_or(self.default)
+    }
+}
+
+/// Fixes the data directory, preferring `command_line` over every other
+/// source. Has no effect once `data_dir` has been called.
+pub fn init_data_dir(command_line: Option<PathBuf>) {
+    let _ = DATA_DIR.set(DataDirSources::read(command_line).resolve());
+}
+
+pub fn data_dir() -> PathBuf {
+    DATA_DIR
+        .get_or_init(|| DataDirSources::read(None).resolve())
+        .clone()
+}
+
+/// Replaces `path` in one step, so that readers never see a partial file.
+/// The content is readable by the owner only where the OS supports it.
+pub fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&tmp_path)?.write_all(content.as_bytes())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// `logLevel` in `config.json`.
+pub fn config_log_level() -> Option<String> {
+    read_config(&project_dirs()?)?.log_level
+}
+
+/// `debug` in `config.json`.
+pub fn config_debug() -> bool {
+    project_dirs()
+        .and_then(|x| read_config(&x))
+        .is_some_and(|x| x.debug)
+}
+
+#[test]
+fn test_data_dir_precedence() {
+    let sources = || DataDirSources {
+        env: Some("env".into()),
+        portable: Some("portable".into()),
+        config: Some("config".into()),
+        default: "default".into(),
+        ..Default::default()
+    };
+    assert_eq!(sources().resolve(), PathBuf::from("env"));
+    assert_eq!(
+        DataDirSources {
+            command_line: Some("command_line".into()),
+            ..sources()
+        }
+        .resolve(),
+        PathBuf::from("command_line")
+    );
+    assert_eq!(
+        DataDirSources {
+            env: None,
+            ..sources()
+        }
+        .resolve(),
+        PathBuf::from("portable")
+    );
+    assert_eq!(
+        DataDirSources {
+            default: "default".into(),
+            ..Default::default()
+        }
+        .resolve(),
+        PathBuf::from("default")
+    );
+}
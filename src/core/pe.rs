@@ -0,0 +1,113 @@
+/// Just enough of the PE format to find the sections of a 32-bit
+/// GameAssembly.dll, either as a file or as mapped by the loader.
+
+#[derive(Debug)]
+pub enum PeError {
+    TooShort,
+    NoDosSignature,
+    NoPeSignature,
+    /// Only PE32 images are supported. Holds the optional header magic.
+    UnsupportedFormat(u16),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub raw_offset: u32,
+    pub raw_size: u32,
+}
+
+#[derive(Debug)]
+pub struct PeHeaders {
+    pub image_base: u32,
+    pub size_of_image: u32,
+    pub sections: Vec<Section>,
+}
+
+impl PeHeaders {
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|x| x.name == name)
+    }
+}
+
+const PE32_MAGIC: u16 = 0x10b;
+const SECTION_HEADER_SIZE: usize = 40;
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, PeError> {
+    let x = bytes.get(offset..offset + 2).ok_or(PeError::TooShort)?;
+    Ok(u16::from_le_bytes([x[0], x[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, PeError> {
+    let x = bytes.get(offset..offset + 4).ok_or(PeError::TooShort)?;
+    Ok(u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+}
+
+/// Parses the headers at the start of `bytes`. Headers are laid out the same
+/// on disk and in memory, so either works.
+pub fn parse(bytes: &[u8]) -> Result<PeHeaders, PeError> {
+    if bytes.get(0..2) != Some(b"MZ") {
+        return Err(PeError::NoDosSignature);
+    }
+    let pe = u32_at(bytes, 0x3c)? as usize;
+    if bytes.get(pe..pe + 4) != Some(b"PE\0\0") {
+        return Err(PeError::NoPeSignature);
+    }
+    let coff = pe + 4;
+    let number_of_sections = u16_at(bytes, coff + 2)? as usize;
+    let size_of_optional_header = u16_at(bytes, coff + 16)? as usize;
+    let optional = coff + 20;
+    let magic = u16_at(bytes, optional)?;
+    if magic != PE32_MAGIC {
+        return Err(PeError::UnsupportedFormat(magic));
+    }
+    let image_base = u32_at(bytes, optional + 28)?;
+    let size_of_image = u32_at(bytes, optional + 56)?;
+    let section_table = optional + size_of_optional_header;
+    let sections = (0..number_of_sections)
+        .map(|i| {
+            let header = section_table + i * SECTION_HEADER_SIZE;
+            let name = bytes
+                .get(header..header + 8)
+                .ok_or(PeError::TooShort)?
+                .iter()
+                .take_while(|x| **x != 0)
+                .map(|x| *x as char)
+                .collect();
+            Ok(Section {
+                name,
+                virtual_size: u32_at(bytes, header + 8)?,
+                virtual_address: u32_at(bytes, header + 12)?,
+                raw_size: u32_at(bytes, header + 16)?,
+                raw_offset: u32_at(bytes, header + 20)?,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(PeHeaders {
+        image_base,
+        size_of_image,
+        sections,
+    })
+}
+
+#[test]
+fn test_parse() {
+    let headers = parse(include_bytes!("fixtures/signature_scan.dll")).unwrap();
+    assert_eq!(headers.image_base, 0x10000000);
+    assert_eq!(headers.size_of_image, 0x3000);
+    assert_eq!(
+        headers.section(".text"),
+        Some(&Section {
+            name: ".text".into(),
+            virtual_address: 0x1000,
+            virtual_size: 0x200,
+            raw_offset: 0x400,
+            raw_size: 0x200,
+        })
+    );
+    assert!(headers.section(".data").is_some());
+    assert!(matches!(parse(b"MZ"), Err(PeError::TooShort)));
+    assert!(matches!(parse(b"not a dll"), Err(PeError::NoDosSignature)));
+}
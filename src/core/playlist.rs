@@ -0,0 +1,76 @@
+use rand::{seq::SliceRandom, thread_rng};
+
+pub struct Playlist {
+    order: Vec<usize>,
+    position: usize,
+    shuffle: bool,
+    started: bool,
+}
+
+impl Playlist {
+    pub fn new(indices: Vec<usize>, shuffle: bool) -> Self {
+        let mut playlist = Self {
+            order: indices,
+            position: 0,
+            shuffle,
+            started: false,
+        };
+        if shuffle {
+            playlist.order.shuffle(&mut thread_rng());
+        }
+        playlist
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        self.order.get(self.position).copied()
+    }
+
+    /// Moves to the next preset, starting over (and reshuffling) at the end.
+    pub fn advance(&mut self) -> Option<usize> {
+        self.position += 1;
+        if self.position >= self.order.len() {
+            self.position = 0;
+            if self.shuffle {
+                self.order.shuffle(&mut thread_rng());
+            }
+        }
+        self.current()
+    }
+
+    /// Returns the preset to play next: the first one on the first call, then
+    /// the following ones as `advance` does.
+    pub fn next(&mut self) -> Option<usize> {
+        if self.started {
+            return self.advance();
+        }
+        self.started = true;
+        self.current()
+    }
+}
+
+#[test]
+fn test_advance() {
+    let mut playlist = Playlist::new(vec![3, 1, 4], false);
+    assert_eq!(playlist.current(), Some(3));
+    assert_eq!(playlist.advance(), Some(1));
+    assert_eq!(playlist.advance(), Some(4));
+    assert_eq!(playlist.advance(), Some(3));
+
+    let mut playlist = Playlist::new(vec![0, 1, 2, 3], true);
+    let mut played: Vec<_> = playlist.current().into_iter().collect();
+    played.extend((0..3).filter_map(|_| playlist.advance()));
+    played.sort_unstable();
+    assert_eq!(played, vec![0, 1, 2, 3]);
+
+    assert_eq!(Playlist::new(vec![], false).advance(), None);
+}
+
+#[test]
+fn test_next() {
+    let mut playlist = Playlist::new(vec![3, 1, 4], false);
+    assert_eq!(playlist.next(), Some(3));
+    assert_eq!(playlist.next(), Some(1));
+    assert_eq!(playlist.current(), Some(1));
+
+    assert_eq!(Playlist::new(vec![], false).next(), None);
+}
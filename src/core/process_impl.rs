@@ -27,6 +27,7 @@ use winapi::{
 
 pub struct Process {
     process: HANDLE,
+    pid: DWORD,
     // old_protect: DWORD,
 }
 
@@ -43,12 +44,17 @@ impl Process {
             } else {
                 Some(Process {
                     process: OpenProcess(PROCESS_ALL_ACCESS, FALSE, process_id),
+                    pid: process_id,
                     // old_protect: 0,
                 })
             }
         }
     }
 
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
     pub fn is_active(&self) -> bool {
         unsafe {
             let mut exit_code: DWORD = 0;
@@ -69,6 +75,11 @@ impl Process {
         String::from_utf16_lossy(&buf).into()
     }
 
+    /// Names and base addresses of the loaded modules.
+    pub fn modules(&self) -> Vec<(String, u32)> {
+        unsafe { module_infos(self.process) }
+    }
+
     pub fn base_addr_of_module_name(&self, module_name: &str) -> Option<u32> {
         unsafe { module_infos(self.process) }
             .into_iter()
@@ -121,6 +132,16 @@ impl Process {
         }
     }
 
+    pub fn read_bytes(&self, address: u32, len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; len];
+        if len > 0 {
+            unsafe {
+                read_process_memory(self.process, address, &mut buf);
+            }
+        }
+        buf
+    }
+
     pub fn write_u8(&self, address: u32, value: u8) {
         let buf = value.to_le_bytes();
         unsafe {
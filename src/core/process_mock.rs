@@ -5,10 +5,26 @@ impl Process {
         Some(Process {})
     }
 
+    pub fn pid(&self) -> u32 {
+        0
+    }
+
+    pub fn is_active(&self) -> bool {
+        true
+    }
+
     pub fn path(&self) -> String {
         "".into()
     }
 
+    pub fn modules(&self) -> Vec<(String, u32)> {
+        Vec::new()
+    }
+
+    pub fn base_addr_of_module_name(&self, _module_name: &str) -> Option<u32> {
+        Some(0)
+    }
+
     pub fn read_u32(&self, _address: u32) -> u32 {
         0
     }
@@ -29,6 +45,10 @@ impl Process {
         0.0
     }
 
+    pub fn read_bytes(&self, _address: u32, len: usize) -> Vec<u8> {
+        vec![0; len]
+    }
+
     pub fn write_u8(&self, _address: u32, _value: u8) {}
 
     pub fn write_i32(&self, _address: u32, _value: i32) {}
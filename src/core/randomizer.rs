@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Deserialize;
+#[cfg(test)]
+use ts_rs::TS;
+
+use super::game_settings::{GameSettings, GameSettingsField};
+
+const TASK_FIELDS: [GameSettingsField; 3] = [
+    GameSettingsField::CommonTasks,
+    GameSettingsField::LongTasks,
+    GameSettingsField::ShortTasks,
+];
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+pub struct Bounds {
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RandomizerConstraints {
+    #[serde(default)]
+    #[cfg_attr(test, ts(as = "Option<HashMap<GameSettingsField, Bounds>>", optional))]
+    pub bounds: HashMap<GameSettingsField, Bounds>,
+    #[cfg_attr(test, ts(optional))]
+    pub max_total_tasks: Option<i32>,
+    #[cfg_attr(test, ts(type = "number", optional))]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum RandomizerError {
+    EmptyRange(GameSettingsField),
+    TooManyTasks,
+    Invalid(Vec<GameSettingsField>),
+}
+
+/// Legal values of `field` within the user bounds, in ascending order.
+fn candidates(field: GameSettingsField, bounds: Option<&Bounds>) -> Vec<f32> {
+    let range = field.range();
+    let count = ((range.max - range.min) / range.step).round() as usize + 1;
+    (0..count)
+        .map(|i| range.min + range.step * i as f32)
+        .filter(|x| bounds.is_none_or(|b| b.min <= *x && *x <= b.max))
+        .collect()
+}
+
+pub fn generate(constraints: &RandomizerConstraints) -> Result<GameSettings, RandomizerError> {
+    let mut rng = match constraints.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut game_settings = GameSettings::default();
+    for field in GameSettingsField::ALL.iter() {
+        let candidates = candidates(*field, constraints.bounds.get(field));
+        let value = *candidates
+            .choose(&mut rng)
+            .ok_or(RandomizerError::EmptyRange(*field))?;
+        game_settings.set_value(*field, value);
+    }
+    if let Some(max_total_tasks) = constraints.max_total_tasks {
+        loop {
+            let total: f32 = TASK_FIELDS.iter().map(|x| game_settings.value(*x)).sum();
+            if total as i32 <= max_total_tasks {
+                break;
+            }
+            let reducible: Vec<_> = TASK_FIELDS
+                .iter()
+                .copied()
+                .filter(|x| candidates(*x, constraints.bounds.get(x))[0] < game_settings.value(*x))
+                .collect();
+            let field = *reducible
+                .choose(&mut rng)
+                .ok_or(RandomizerError::TooManyTasks)?;
+            let value = game_settings.value(field) - field.range().step;
+            game_settings.set_value(field, value);
+        }
+    }
+    let invalid_fields = game_settings.invalid_fields();
+    if !invalid_fields.is_empty() {
+        return Err(RandomizerError::Invalid(invalid_fields));
+    }
+    Ok(game_settings)
+}
+
+#[test]
+fn test_generate_within_constraints() {
+    let mut bounds = HashMap::new();
+    bounds.insert(
+        GameSettingsField::PlayerSpeed,
+        Bounds { min: 1.0, max: 2.0 },
+    );
+    let constraints = RandomizerConstraints {
+        bounds,
+        max_total_tasks: Some(4),
+        seed: Some(42),
+    };
+    for _ in 0..10 {
+        let game_settings = generate(&constraints).unwrap();
+        assert!(game_settings.invalid_fields().is_empty());
+        assert!(1.0 <= game_settings.player_speed && game_settings.player_speed <= 2.0);
+        assert!(
+            game_settings.common_tasks + game_settings.long_tasks + game_settings.short_tasks <= 4
+        );
+    }
+    let a = generate(&constraints).unwrap();
+    let b = generate(&constraints).unwrap();
+    assert_eq!(format!("{:?}", a), format!("{:?}", b));
+}
+
+#[test]
+fn test_generate_rejects_empty_range() {
+    let mut bounds = HashMap::new();
+    bounds.insert(
+        GameSettingsField::KillCooldown,
+        Bounds {
+            min: 61.0,
+            max: 70.0,
+        },
+    );
+    let constraints = RandomizerConstraints {
+        bounds,
+        ..Default::default()
+    };
+    assert!(matches!(
+        generate(&constraints),
+        Err(RandomizerError::EmptyRange(GameSettingsField::KillCooldown))
+    ));
+}
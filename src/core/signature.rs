@@ -0,0 +1,240 @@
+use std::fs;
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use super::{
+    paths::data_dir,
+    pe::{self, PeError, PeHeaders},
+    process::Process,
+};
+
+/// A byte pattern such as `8B 0D ?? ?? ?? ?? 8B 49 5C`, where `??` matches
+/// any byte.
+#[derive(Debug, PartialEq)]
+pub struct Pattern(Vec<Option<u8>>);
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let bytes = pattern
+            .split_whitespace()
+            .map(|x| match x {
+                "?" | "??" => Some(None),
+                x => u8::from_str_radix(x, 16).ok().map(Some),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if bytes.is_empty() {
+            return None;
+        }
+        Some(Self(bytes))
+    }
+
+    /// Position of every match in `haystack`.
+    pub fn find_all<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        haystack
+            .windows(self.0.len())
+            .enumerate()
+            .filter(move |(_, window)| {
+                self.0
+                    .iter()
+                    .zip(window.iter())
+                    .all(|(x, y)| x.is_none_or(|x| x == *y))
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+/// An instruction that references the GameOptions static field by absolute
+/// address.
+#[derive(Debug, Deserialize)]
+pub struct Signature {
+    pub name: String,
+    pub pattern: String,
+    /// Position of the 4-byte address within the match.
+    pub operand: usize,
+}
+
+fn bundled_signatures() -> Vec<Signature> {
+    serde_json::from_str(include_str!("signatures.json")).unwrap()
+}
+
+/// Bundled signatures followed by those in `signatures.json` in the data
+/// directory, which can be added without waiting for a release.
+static SIGNATURES: Lazy<Vec<Signature>> = Lazy::new(|| {
+    let mut signatures = bundled_signatures();
+    if let Ok(json) = fs::read_to_string(data_dir().join("signatures.json")) {
+        match serde_json::from_str::<Vec<Signature>>(&json) {
+            Err(err) => warn!("Signature: invalid signatures.json. {}", err),
+            Ok(x) => signatures.extend(x),
+        }
+    }
+    signatures
+});
+
+/// Searches `code` for the first signature whose matches all agree on one
+/// address inside the image, and returns that address relative to
+/// `image_base`.
+fn scan(code: &[u8], image_base: u32, size_of_image: u32, signatures: &[Signature]) -> Option<u32> {
+    signatures.iter().find_map(|signature| {
+        let pattern = match Pattern::parse(&signature.pattern) {
+            None => {
+                warn!("Signature: invalid pattern {:?}", signature.name);
+                return None;
+            }
+            Some(x) => x,
+        };
+        let mut offsets = pattern.find_all(code).filter_map(|i| {
+            let x = code.get(i + signature.operand..i + signature.operand + 4)?;
+            let address = u32::from_le_bytes([x[0], x[1], x[2], x[3]]);
+            address
+                .checked_sub(image_base)
+                .filter(|x| *x < size_of_image)
+        });
+        let offset = offsets.next()?;
+        if offsets.any(|x| x != offset) {
+            debug!("Signature: {} is ambiguous", signature.name);
+            return None;
+        }
+        info!(
+            "Signature: {} found GameOptions at {:#x}",
+            signature.name, offset
+        );
+        Some(offset)
+    })
+}
+
+/// Finds `GameOptionsOffset` in the bytes of GameAssembly.dll as stored on
+/// disk.
+pub fn game_options_offset_in_file(dll: &[u8], signatures: &[Signature]) -> Option<u32> {
+    let headers = match pe::parse(dll) {
+        Err(PeError::UnsupportedFormat(magic)) => {
+            debug!(
+                "Signature: unsupported PE optional header magic {:#x}",
+                magic
+            );
+            return None;
+        }
+        Err(err) => {
+            debug!("Signature: GameAssembly.dll not parsed. {:?}", err);
+            return None;
+        }
+        Ok(x) => x,
+    };
+    let text = headers.section(".text")?;
+    let start = text.raw_offset as usize;
+    let code = dll.get(start..start + text.raw_size as usize)?;
+    scan(code, headers.image_base, headers.size_of_image, signatures)
+}
+
+/// Finds `GameOptionsOffset` in GameAssembly.dll as loaded at `base_addr`.
+/// The loader has relocated the addresses in the code to `base_addr`.
+pub fn game_options_offset_in_memory(
+    read: impl Fn(u32, usize) -> Vec<u8>,
+    base_addr: u32,
+    signatures: &[Signature],
+) -> Option<u32> {
+    let headers: PeHeaders = pe::parse(&read(base_addr, 0x1000)).ok()?;
+    let text = headers.section(".text")?;
+    let code = read(
+        base_addr.wrapping_add(text.virtual_address),
+        text.virtual_size as usize,
+    );
+    scan(&code, base_addr, headers.size_of_image, signatures)
+}
+
+/// Locates `GameOptionsOffset` without Offsets.json, trying the file at
+/// `dll_path` first and then the running process. Reads the whole DLL.
+pub fn game_options_offset(dll_path: &str, process: &Process) -> Option<u32> {
+    if SIGNATURES.is_empty() {
+        return None;
+    }
+    let in_file = fs::read(dll_path)
+        .ok()
+        .and_then(|x| game_options_offset_in_file(&x, &SIGNATURES));
+    in_file.or_else(|| {
+        let base_addr = process.base_addr_of_module_name("GameAssembly.dll")?;
+        game_options_offset_in_memory(
+            |address, len| process.read_bytes(address, len),
+            base_addr,
+            &SIGNATURES,
+        )
+    })
+}
+
+#[cfg(test)]
+fn test_signatures() -> Vec<Signature> {
+    serde_json::from_str(
+        r#"[
+            { "name": "broken", "pattern": "8B 0D ?? ZZ", "operand": 2 },
+            { "name": "absent", "pattern": "A1 ?? ?? ?? ?? 85 C0", "operand": 1 },
+            { "name": "GameOptions", "pattern": "8B 0D ?? ?? ?? ?? 8B 49 5C", "operand": 2 }
+        ]"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_bundled_signatures() {
+    for signature in bundled_signatures() {
+        let pattern = Pattern::parse(&signature.pattern).unwrap();
+        assert!(
+            signature.operand + 4 <= pattern.0.len(),
+            "{}",
+            signature.name
+        );
+    }
+}
+
+#[test]
+fn test_pattern() {
+    let pattern = Pattern::parse("8B ?? 5C").unwrap();
+    assert_eq!(pattern, Pattern(vec![Some(0x8b), None, Some(0x5c)]));
+    let haystack = [0x00, 0x8b, 0x01, 0x5c, 0x8b, 0x02, 0x5c, 0x8b];
+    assert_eq!(pattern.find_all(&haystack).collect::<Vec<_>>(), [1, 4]);
+    assert!(Pattern::parse("").is_none());
+    assert!(Pattern::parse("8B GG").is_none());
+}
+
+#[test]
+fn test_scan_file() {
+    let dll = include_bytes!("fixtures/signature_scan.dll");
+    assert_eq!(
+        game_options_offset_in_file(dll, &test_signatures()),
+        Some(0x2010)
+    );
+    assert_eq!(game_options_offset_in_file(dll, &[]), None);
+    assert_eq!(
+        game_options_offset_in_file(&dll[..0x300], &test_signatures()),
+        None
+    );
+}
+
+#[test]
+fn test_scan_memory() {
+    // Map the fixture the way the loader would, relocated to `base_addr`.
+    let dll = include_bytes!("fixtures/signature_scan.dll");
+    let headers = pe::parse(dll).unwrap();
+    let base_addr = 0x5000_0000;
+    let mut image = vec![0u8; headers.size_of_image as usize];
+    image[..0x400].copy_from_slice(&dll[..0x400]);
+    for section in &headers.sections {
+        let raw = &dll[section.raw_offset as usize..][..section.raw_size as usize];
+        image[section.virtual_address as usize..][..raw.len()].copy_from_slice(raw);
+    }
+    let text = headers.section(".text").unwrap().virtual_address as usize;
+    for operand in &[0x12, 0x42] {
+        let at = text + operand;
+        let address = u32::from_le_bytes([image[at], image[at + 1], image[at + 2], image[at + 3]]);
+        let relocated = address - headers.image_base + base_addr;
+        image[at..at + 4].copy_from_slice(&relocated.to_le_bytes());
+    }
+    let read = |address: u32, len: usize| {
+        let start = (address - base_addr) as usize;
+        image[start..(start + len).min(image.len())].to_vec()
+    };
+    assert_eq!(
+        game_options_offset_in_memory(read, base_addr, &test_signatures()),
+        Some(0x2010)
+    );
+}
@@ -1,33 +1,124 @@
-use std::fs;
+use std::fs::{self, File, OpenOptions};
 use std::path::PathBuf;
 
-use anyhow::Result;
-use directories_next::ProjectDirs;
+use anyhow::{bail, Result};
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+#[cfg(test)]
+use ts_rs::TS;
 
-use super::game_settings::GameSettings;
+use super::{
+    game_settings::{GameSettings, GameSettingsField},
+    game_version::GameVersion,
+    paths::{data_dir, write_atomically},
+};
 
 fn data_path() -> PathBuf {
-    let mut path = if let Some(project_dirs) = ProjectDirs::from("net", "prgrssv", "ausettings") {
-        project_dirs.data_dir().into()
-    } else {
-        std::env::current_dir().unwrap_or(std::path::PathBuf::new())
-    };
-    path.push("ausettings.json");
-    path
+    data_dir().join("ausettings.json")
 }
 
-#[derive(Serialize, Deserialize)]
+/// Exclusive advisory lock on the storage, released on drop.
+struct StorageLock(File);
+
+impl StorageLock {
+    fn acquire() -> Result<Self> {
+        let dir = data_dir();
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join("ausettings.lock"))?;
+        file.lock_exclusive()?;
+        Ok(Self(file))
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+fn content_revision(data_str: &str) -> String {
+    hex::encode_upper(Sha256::digest(data_str.as_bytes()))
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
+pub struct PlayerCountRange {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PlayerCountRange {
+    pub fn contains(&self, player_count: usize) -> bool {
+        self.min <= player_count && player_count <= self.max
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(TS), ts(export))]
 #[serde(rename_all = "camelCase")]
 pub struct GameSettingsListItem {
     pub name: String,
     pub game_settings: Option<GameSettings>,
+    /// Fields applied on load. `None` means every field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub fields: Option<Vec<GameSettingsField>>,
+    /// Name of the preset whose resolved settings this one overrides.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub parent: Option<String>,
+    /// Lobby sizes this preset is meant for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub player_count: Option<PlayerCountRange>,
+    /// Game version the settings were captured on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub game_version: Option<GameVersion>,
+}
+
+impl GameSettingsListItem {
+    pub fn fields(&self) -> &[GameSettingsField] {
+        self.fields.as_deref().unwrap_or(&GameSettingsField::ALL)
+    }
+}
+
+const BUILTIN_PRESETS: &str = include_str!("builtin_presets.json");
+
+/// Read-only presets bundled with the binary.
+pub fn builtin_presets() -> Vec<GameSettingsListItem> {
+    serde_json::from_str(BUILTIN_PRESETS).unwrap()
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    NoSuchPreset(usize),
+    NoData(String),
+    MissingParent(String),
+    Cycle(Vec<String>),
+    /// Another preset already has this name.
+    NameTaken(String),
+    Io(String),
+    /// ausettings.json does not parse. Nothing is saved until it does.
+    Malformed(String),
+    /// ausettings.json changed since the client last read it.
+    Stale,
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Storage {
     pub game_settings_list: Vec<GameSettingsListItem>,
+    #[serde(default)]
+    pub auto_apply_recommended: bool,
+    /// Hash of the file content this was loaded from.
+    #[serde(skip)]
+    revision: Option<String>,
 }
 
 impl Default for Storage {
@@ -36,23 +127,454 @@ impl Default for Storage {
             game_settings_list: (0..10)
                 .map(|i| GameSettingsListItem {
                     name: format!("Settings {}", i + 1),
-                    game_settings: None,
+                    ..Default::default()
                 })
                 .collect(),
+            auto_apply_recommended: false,
+            revision: None,
         }
     }
 }
 
 impl Storage {
-    pub fn load() -> Self {
-        let data_str = fs::read_to_string(&data_path()).unwrap_or_default();
-        serde_json::from_str(&data_str).unwrap_or_default()
+    /// Loads ausettings.json, or the defaults if there is no file.
+    pub fn load() -> Result<Self, StorageError> {
+        let data_str = match fs::read_to_string(data_path()) {
+            Err(_) => return Ok(Self::default()),
+            Ok(x) => x,
+        };
+        let storage: Self = serde_json::from_str(&data_str)
+            .map_err(|err| StorageError::Malformed(err.to_string()))?;
+        Ok(Self {
+            revision: Some(content_revision(&data_str)),
+            ..storage
+        })
+    }
+
+    /// Hash of the current file content, or `None` if there is no file.
+    pub fn file_revision() -> Option<String> {
+        fs::read_to_string(data_path())
+            .ok()
+            .map(|x| content_revision(&x))
     }
 
-    pub fn save(&self) -> Result<()> {
+    /// Hash of the file content this was loaded from.
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    /// Fails without writing if the file was changed since `load`. Returns
+    /// the revision of the written content.
+    pub fn save(&self) -> Result<String> {
+        if Self::file_revision() != self.revision {
+            bail!("ausettings.json was changed by another program");
+        }
         let json = serde_json::to_string_pretty(self)?;
         let data_path = data_path();
-        fs::create_dir_all(&data_path.parent().unwrap())?;
-        Ok(fs::write(&data_path, &json)?)
+        fs::create_dir_all(data_path.parent().unwrap())?;
+        // Readers do not take the lock, so never let them see a partial file.
+        write_atomically(&data_path, &json)?;
+        Ok(content_revision(&json))
+    }
+
+    /// Loads, modifies and saves the storage while holding the lock, so that
+    /// other instances cannot interleave their own changes. `revision` is the
+    /// one the client last saw; the change is refused if the file has moved
+    /// on since. Returns the new revision.
+    pub fn update(
+        revision: Option<&str>,
+        f: impl FnOnce(&mut Storage) -> Result<(), StorageError>,
+    ) -> Result<String, StorageError> {
+        let io_error = |err: anyhow::Error| StorageError::Io(err.to_string());
+        let _lock = StorageLock::acquire().map_err(io_error)?;
+        let mut storage = Self::load()?;
+        if revision.is_some() && revision != storage.revision() {
+            return Err(StorageError::Stale);
+        }
+        f(&mut storage)?;
+        storage.save().map_err(io_error)
+    }
+
+    pub fn slot(&self, idx: usize) -> Result<&GameSettingsListItem, StorageError> {
+        self.game_settings_list
+            .get(idx)
+            .ok_or(StorageError::NoSuchPreset(idx))
+    }
+
+    pub fn slot_mut(&mut self, idx: usize) -> Result<&mut GameSettingsListItem, StorageError> {
+        self.game_settings_list
+            .get_mut(idx)
+            .ok_or(StorageError::NoSuchPreset(idx))
+    }
+
+    /// Returns the preset at `idx` followed by its ancestors, nearest first.
+    fn lineage(&self, idx: usize) -> Result<Vec<&GameSettingsListItem>, StorageError> {
+        let mut lineage = vec![self.slot(idx)?];
+        while let Some(parent_name) = &lineage.last().unwrap().parent {
+            let parent = self
+                .game_settings_list
+                .iter()
+                .find(|x| &x.name == parent_name)
+                .ok_or_else(|| StorageError::MissingParent(parent_name.clone()))?;
+            if lineage.iter().any(|x| std::ptr::eq(*x, parent)) {
+                let mut names: Vec<_> = lineage.iter().map(|x| x.name.clone()).collect();
+                names.push(parent.name.clone());
+                return Err(StorageError::Cycle(names));
+            }
+            lineage.push(parent);
+        }
+        Ok(lineage)
+    }
+
+    /// Resolves the preset at `idx` against its parents and returns the
+    /// settings together with every field that the chain defines.
+    pub fn resolve(
+        &self,
+        idx: usize,
+    ) -> Result<(GameSettings, Vec<GameSettingsField>), StorageError> {
+        let lineage = self.lineage(idx)?;
+        let mut game_settings = GameSettings::default();
+        let mut fields: Vec<GameSettingsField> = Vec::new();
+        for item in lineage.iter().rev() {
+            let item_game_settings = match &item.game_settings {
+                Some(x) => x,
+                None if item.parent.is_some() => continue,
+                None => return Err(StorageError::NoData(item.name.clone())),
+            };
+            game_settings.merge(item_game_settings, item.fields());
+            for field in item.fields() {
+                if !fields.contains(field) {
+                    fields.push(*field);
+                }
+            }
+        }
+        Ok((game_settings, fields))
+    }
+
+    pub fn validate_parent(&self, idx: usize) -> Result<(), StorageError> {
+        self.lineage(idx).map(|_| ())
+    }
+
+    /// Renames the preset at `idx` and points its children at the new name.
+    pub fn rename(&mut self, idx: usize, name: String) -> Result<(), StorageError> {
+        let old_name = self.slot(idx)?.name.clone();
+        if self
+            .game_settings_list
+            .iter()
+            .enumerate()
+            .any(|(i, x)| i != idx && x.name == name)
+        {
+            return Err(StorageError::NameTaken(name));
+        }
+        // Children look their parent up by name, so only the first preset
+        // with a duplicated name from older files has any.
+        let is_parent = self
+            .game_settings_list
+            .iter()
+            .position(|x| x.name == old_name)
+            == Some(idx);
+        if is_parent {
+            for item in &mut self.game_settings_list {
+                if item.parent.as_deref() == Some(&old_name) {
+                    item.parent = Some(name.clone());
+                }
+            }
+        }
+        self.game_settings_list[idx].name = name;
+        Ok(())
+    }
+
+    /// Replaces preset names, which may be personal, with `Preset N`. Parent
+    /// references keep pointing at the same presets.
+    pub fn anonymized(mut self) -> Self {
+        let names: Vec<String> = self
+            .game_settings_list
+            .iter()
+            .map(|x| x.name.clone())
+            .collect();
+        let anonymize = |name: &str| match names.iter().position(|x| x == name) {
+            Some(i) => format!("Preset {}", i + 1),
+            None => "(missing)".into(),
+        };
+        for (i, item) in self.game_settings_list.iter_mut().enumerate() {
+            item.name = format!("Preset {}", i + 1);
+            item.parent = item.parent.as_deref().map(anonymize);
+        }
+        self
     }
+
+    /// Returns the loadable preset with the narrowest player count range that
+    /// covers `player_count`, preferring the earlier one on ties.
+    pub fn recommend(&self, player_count: usize) -> Option<usize> {
+        self.game_settings_list
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| {
+                let range = x.player_count?;
+                if !range.contains(player_count) || self.resolve(i).is_err() {
+                    return None;
+                }
+                Some((range.max - range.min, i))
+            })
+            .min()
+            .map(|(_, i)| i)
+    }
+}
+
+#[test]
+fn test_builtin_presets() {
+    let presets = builtin_presets();
+    assert!(!presets.is_empty());
+    assert!(presets.iter().all(|x| x.game_settings.is_some()));
+}
+
+#[cfg(test)]
+fn test_item(name: &str, player_speed: f32, parent: Option<&str>) -> GameSettingsListItem {
+    GameSettingsListItem {
+        name: name.into(),
+        game_settings: Some(GameSettings {
+            player_speed,
+            voting_time: 120,
+            ..Default::default()
+        }),
+        fields: parent.map(|_| vec![GameSettingsField::PlayerSpeed]),
+        parent: parent.map(|x| x.into()),
+        player_count: None,
+        game_version: None,
+    }
+}
+
+#[test]
+fn test_resolve_overrides_parent() {
+    let storage = Storage {
+        game_settings_list: vec![
+            test_item("base", 1.0, None),
+            test_item("fast", 2.0, Some("base")),
+        ],
+        ..Default::default()
+    };
+    let (game_settings, fields) = storage.resolve(1).unwrap();
+    assert_eq!(game_settings.player_speed, 2.0);
+    assert_eq!(game_settings.voting_time, 120);
+    assert_eq!(fields.len(), GameSettingsField::ALL.len());
+}
+
+#[test]
+fn test_resolve_detects_cycle_and_missing_parent() {
+    let storage = Storage {
+        game_settings_list: vec![
+            test_item("a", 1.0, Some("b")),
+            test_item("b", 1.0, Some("a")),
+            test_item("c", 1.0, Some("missing")),
+        ],
+        ..Default::default()
+    };
+    assert!(matches!(storage.resolve(0), Err(StorageError::Cycle(_))));
+    assert!(matches!(
+        storage.resolve(2),
+        Err(StorageError::MissingParent(x)) if x == "missing"
+    ));
+}
+
+#[test]
+fn test_rename() {
+    let mut storage = Storage {
+        game_settings_list: vec![
+            test_item("base", 1.0, None),
+            test_item("child", 2.0, Some("base")),
+            test_item("other", 3.0, None),
+        ],
+        ..Default::default()
+    };
+    storage.rename(0, "renamed".into()).unwrap();
+    assert_eq!(
+        storage.game_settings_list[1].parent.as_deref(),
+        Some("renamed")
+    );
+    assert!(storage.resolve(1).is_ok());
+    assert!(matches!(
+        storage.rename(2, "child".into()),
+        Err(StorageError::NameTaken(x)) if x == "child"
+    ));
+    assert_eq!(storage.game_settings_list[2].name, "other");
+    storage.rename(1, "child".into()).unwrap();
+}
+
+#[test]
+fn test_recommend() {
+    let with_range = |name, min, max| GameSettingsListItem {
+        player_count: Some(PlayerCountRange { min, max }),
+        ..test_item(name, 1.0, None)
+    };
+    let storage = Storage {
+        game_settings_list: vec![
+            with_range("any", 4, 15),
+            with_range("small", 4, 6),
+            with_range("large", 10, 15),
+            GameSettingsListItem {
+                player_count: Some(PlayerCountRange { min: 4, max: 5 }),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    assert_eq!(storage.recommend(5), Some(1));
+    assert_eq!(storage.recommend(8), Some(0));
+    assert_eq!(storage.recommend(12), Some(2));
+    assert_eq!(storage.recommend(3), None);
+}
+
+#[test]
+fn test_slot_out_of_range() {
+    let mut storage = Storage::default();
+    let len = storage.game_settings_list.len();
+    assert!(storage.slot(len - 1).is_ok());
+    assert!(matches!(storage.slot(len), Err(StorageError::NoSuchPreset(x)) if x == len));
+    assert!(matches!(
+        storage.slot_mut(usize::MAX),
+        Err(StorageError::NoSuchPreset(_))
+    ));
+    assert!(matches!(
+        storage.resolve(len),
+        Err(StorageError::NoSuchPreset(_))
+    ));
+}
+
+#[test]
+fn test_slot_after_shrink() {
+    // Another instance removed slots after the index was handed out.
+    let mut storage = Storage {
+        game_settings_list: vec![
+            test_item("base", 1.0, None),
+            test_item("fast", 2.0, Some("base")),
+            test_item("slow", 0.5, Some("base")),
+        ],
+        ..Default::default()
+    };
+    let idx = 2;
+    storage.game_settings_list.truncate(1);
+    assert!(matches!(
+        storage.slot(idx),
+        Err(StorageError::NoSuchPreset(2))
+    ));
+    assert!(matches!(
+        storage.resolve(idx),
+        Err(StorageError::NoSuchPreset(2))
+    ));
+    assert!(storage.validate_parent(idx).is_err());
+}
+
+#[test]
+fn test_anonymized() {
+    let storage = Storage {
+        game_settings_list: vec![
+            test_item("alice's base", 1.0, None),
+            test_item("fast", 2.0, Some("alice's base")),
+            test_item("orphan", 1.0, Some("bob")),
+        ],
+        ..Default::default()
+    }
+    .anonymized();
+    let names: Vec<_> = storage
+        .game_settings_list
+        .iter()
+        .map(|x| (x.name.as_str(), x.parent.as_deref()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("Preset 1", None),
+            ("Preset 2", Some("Preset 1")),
+            ("Preset 3", Some("(missing)")),
+        ]
+    );
+    assert_eq!(
+        storage.game_settings_list[1]
+            .game_settings
+            .as_ref()
+            .unwrap()
+            .player_speed,
+        2.0
+    );
+}
+
+/// Points the data directory at a scratch directory. Tests that touch
+/// ausettings.json share it and run one at a time while holding the guard.
+#[cfg(test)]
+fn test_data_dir() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|x| x.into_inner());
+    let dir = std::env::temp_dir().join(format!("ausettings-test-{}", std::process::id()));
+    super::paths::init_data_dir(Some(dir.clone()));
+    assert_eq!(data_dir(), dir);
+    let _ = fs::remove_file(data_path());
+    guard
+}
+
+#[test]
+fn test_update_revision() {
+    let _guard = test_data_dir();
+    let rename = |name: &'static str| {
+        move |storage: &mut Storage| {
+            storage.slot_mut(0)?.name = name.into();
+            Ok(())
+        }
+    };
+    let revision = Storage::update(None, rename("a")).unwrap();
+    assert_eq!(Storage::file_revision().as_ref(), Some(&revision));
+    let new_revision = Storage::update(Some(&revision), rename("b")).unwrap();
+    assert!(matches!(
+        Storage::update(Some(&revision), rename("c")),
+        Err(StorageError::Stale)
+    ));
+    assert_eq!(Storage::load().unwrap().revision(), Some(&*new_revision));
+    assert_eq!(Storage::load().unwrap().game_settings_list[0].name, "b");
+}
+
+#[test]
+fn test_malformed_file() {
+    let _guard = test_data_dir();
+    fs::create_dir_all(data_dir()).unwrap();
+    fs::write(data_path(), "{").unwrap();
+    assert!(matches!(Storage::load(), Err(StorageError::Malformed(_))));
+    assert!(matches!(
+        Storage::update(None, |_| Ok(())),
+        Err(StorageError::Malformed(_))
+    ));
+    assert_eq!(fs::read_to_string(data_path()).unwrap(), "{");
+}
+
+#[test]
+fn test_update_after_file_shrink() {
+    let _guard = test_data_dir();
+    let write = |game_settings_list| {
+        fs::create_dir_all(data_dir()).unwrap();
+        let storage = Storage {
+            game_settings_list,
+            ..Default::default()
+        };
+        fs::write(data_path(), serde_json::to_string(&storage).unwrap()).unwrap();
+    };
+    write(vec![
+        test_item("base", 1.0, None),
+        test_item("fast", 2.0, Some("base")),
+        test_item("slow", 0.5, Some("base")),
+    ]);
+    let idx = 2;
+    assert!(Storage::load().unwrap().resolve(idx).is_ok());
+    // Another instance removes slots after the index was handed out.
+    write(vec![test_item("base", 1.0, None)]);
+    let content = fs::read_to_string(data_path()).unwrap();
+    assert!(matches!(
+        Storage::update(None, |storage| {
+            storage.slot_mut(idx)?.name = "renamed".into();
+            Ok(())
+        }),
+        Err(StorageError::NoSuchPreset(2))
+    ));
+    assert!(matches!(
+        Storage::load().unwrap().resolve(idx),
+        Err(StorageError::NoSuchPreset(2))
+    ));
+    assert_eq!(fs::read_to_string(data_path()).unwrap(), content);
 }
@@ -6,14 +6,67 @@
 mod core;
 mod ui;
 
-use std::sync::{Arc, Mutex};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
+use log::error;
 use web_view::*;
 
+use crate::core::{
+    instance::{self, Endpoint, Instance},
+    logging::init_logging,
+    paths::{config_debug, init_data_dir},
+};
 use crate::ui::app_wrapper::AppWrapper;
 
 #[tokio::main]
 async fn main() {
+    // Usage: ausettings [--data-dir <path>] [--log-level <spec>] [--debug] [--watch] [<command>...]
+    let mut data_dir = None;
+    let mut log_level = None;
+    let mut debug = false;
+    let mut watch = false;
+    let mut commands = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            data_dir = args.next().map(PathBuf::from);
+        } else if arg == "--log-level" {
+            log_level = args.next();
+        } else if arg == "--debug" {
+            debug = true;
+        } else if arg == "--watch" {
+            watch = true;
+        } else {
+            commands.push(arg.replace('\n', " "));
+        }
+    }
+    init_data_dir(data_dir);
+    let instance = instance::acquire();
+    match instance {
+        Ok(Instance::Secondary(endpoint)) => {
+            attach_console();
+            std::process::exit(run_secondary(&endpoint, &commands, watch));
+        }
+        Ok(Instance::Unresponsive) => {
+            attach_console();
+            eprintln!("Error: another ausettings instance is not responding.");
+            std::process::exit(1);
+        }
+        _ => {}
+    }
+    // Only the primary instance writes the log, so rotation never races.
+    let _logger = init_logging(log_level);
+    let (_instance_lock, instance_listener) = match instance {
+        Err(err) => {
+            error!("Single instance check failed. {}", err);
+            (None, None)
+        }
+        Ok(Instance::Primary(lock, listener, token)) => (Some(lock), Some((listener, token))),
+        Ok(_) => unreachable!(),
+    };
     let app_wrapper: Arc<Mutex<Option<AppWrapper>>> = Arc::new(Mutex::new(None));
     let web_view = web_view::builder()
         .title("ausettings")
@@ -31,6 +84,57 @@ async fn main() {
         })
         .build()
         .unwrap();
-    *(app_wrapper.lock().as_deref_mut().unwrap()) = Some(AppWrapper::new(web_view.handle()));
+    *(app_wrapper.lock().as_deref_mut().unwrap()) = Some(AppWrapper::new(
+        web_view.handle(),
+        instance_listener,
+        debug || config_debug(),
+    ));
     web_view.run().unwrap();
 }
+
+/// Release builds on Windows have no console of their own, so replies go to
+/// the console of the shell that ran the command, if any.
+#[cfg(windows)]
+fn attach_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Forwards `commands` to the running instance and prints each reply as a
+/// JSON line. Returns the exit code: 1 if anything failed.
+fn run_secondary(endpoint: &Endpoint, commands: &[String], watch: bool) -> i32 {
+    // Each command is JSON such as
+    // {"type":"load_memory_from_file","payload":{"index":0}}
+    if commands.is_empty() && !watch {
+        println!("ausettings is already running.");
+        return 0;
+    }
+    let mut exit_code = 0;
+    match instance::forward(endpoint, commands) {
+        Err(err) => {
+            eprintln!("Error: forwarding failed. {}", err);
+            exit_code = 1;
+        }
+        Ok(replies) => {
+            for reply in replies {
+                println!("{}", reply);
+                let is_err = serde_json::from_str::<serde_json::Value>(&reply)
+                    .ok()
+                    .is_none_or(|x| x.get("err").is_some());
+                if is_err {
+                    exit_code = 1;
+                }
+            }
+        }
+    }
+    if watch {
+        if let Err(err) = instance::watch(endpoint, |x| println!("{}", x)) {
+            eprintln!("Error: watching failed. {}", err);
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
@@ -1,84 +1,152 @@
 use std::sync::Arc;
 
-use serde_json::{json, Value};
+use log::{debug, error, trace, warn};
+use serde_json::Value;
 use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedWriteHalf, TcpListener},
     spawn,
-    sync::{mpsc, Mutex},
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        Mutex,
+    },
 };
 use web_view::{Handle, WVResult, WebView};
 
-use crate::core::app::{App, ProcessStatus};
+use crate::core::{
+    app::{App, ApplyError},
+    command::{dispatch, execute, Command, Response},
+    event::Event,
+};
 
 async fn invoke_handler<T>(handle: Handle<T>, arg: &str, app_mutex: &Mutex<App>) {
-    let json: Value = serde_json::from_str(arg).unwrap();
-    let app = app_mutex.lock().await;
-    let payload = &json["payload"];
-    let (err, result) = match json["type"].as_str().unwrap() {
-        "init" => (
-            "null".to_owned(),
-            serde_json::to_string(&app.init().await).unwrap(),
-        ),
-        "open_browser" => {
-            app.open_browser(payload["url"].as_str().unwrap());
-            ("null".to_owned(), "null".to_owned())
-        }
-        "set_game_settings_name" => {
-            let idx = payload["index"].as_u64().unwrap() as usize;
-            let name = payload["name"].as_str().unwrap();
-            app.set_game_settings_name(idx, name.into());
-            ("null".into(), "null".into())
-        }
-        "save_memory_to_file" => {
-            let idx = payload["index"].as_u64().unwrap() as usize;
-            match app.save_memory_to_file(idx).await {
-                Some(_) => ("null".into(), "null".into()),
-                None => (json!({ "name": "Error" }).to_string(), "null".into()),
-            }
+    let callback = match serde_json::from_str::<Value>(arg)
+        .ok()
+        .and_then(|x| x["callback"].as_str().map(|x| x.to_owned()))
+    {
+        None => {
+            error!("Message without callback. {}", arg);
+            return;
         }
-        "load_memory_from_file" => {
-            let idx = payload["index"].as_u64().unwrap() as usize;
-            match app.load_memory_from_file(idx).await {
-                Some(_) => ("null".into(), "null".into()),
-                None => (json!({ "name": "Error" }).to_string(), "null".into()),
-            }
-        }
-        _ => unreachable!(),
+        Some(x) => x,
+    };
+    let response = dispatch(&*app_mutex.lock().await, arg).await;
+    let (err, result) = match response {
+        Response::Ok(value) => ("null".into(), value.to_string()),
+        Response::Err(err) => (serde_json::to_string(&err).unwrap(), "null".into()),
     };
-    let eval = format!(
-        "{}({}, {})",
-        json["callback"].as_str().unwrap(),
-        err,
-        result
-    );
-    println!("<-- {}", eval);
+    let eval = format!("{}({}, {})", callback, err, result);
+    trace!("<-- {}", eval);
     handle
         .dispatch(move |web_view| web_view.eval(&eval))
-        .unwrap_or_else(|err| eprintln!("{}", err));
+        .unwrap_or_else(|err| error!("{}", err));
 }
 
-pub struct AppWrapper {
-    app: Arc<Mutex<App>>,
+fn forward_to_window(handle: Handle<()>, mut rx: Receiver<Event>) {
+    spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+                Ok(x) => x,
+            };
+            let eval = format!("window.onEvent({})", serde_json::to_string(&event).unwrap());
+            trace!("<-- {}", eval);
+            handle
+                .dispatch(move |web_view| web_view.eval(&eval))
+                .unwrap_or_else(|err| error!("{}", err));
+        }
+    });
 }
 
-impl AppWrapper {
-    pub fn new(handle: Handle<()>) -> Self {
-        let (process_status_sender, mut rx) = mpsc::channel::<ProcessStatus>(16);
+/// Writes every event to `writer` as a JSON line until the peer goes away.
+async fn stream_events(mut rx: Receiver<Event>, writer: &mut OwnedWriteHalf) {
+    loop {
+        let event = match rx.recv().await {
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+            Ok(x) => x,
+        };
+        let line = format!("{}\n", serde_json::to_string(&event).unwrap());
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Answers commands forwarded by other ausettings instances, one JSON line
+/// per command and reply, once the connection has sent `token`. A `watch`
+/// command turns the connection into an event stream.
+async fn serve_instance(
+    listener: std::net::TcpListener,
+    token: String,
+    app_mutex: Arc<Mutex<App>>,
+) {
+    listener.set_nonblocking(true).unwrap();
+    let listener = TcpListener::from_std(listener).unwrap();
+    let token = Arc::new(token);
+    loop {
+        let stream = match listener.accept().await {
+            Err(err) => {
+                error!("Instance socket: {}", err);
+                continue;
+            }
+            Ok((stream, _)) => stream,
+        };
+        let token = token.clone();
+        let app_mutex = app_mutex.clone();
         spawn(async move {
-            loop {
-                let process_status = rx.recv().await.unwrap();
-                let eval = format!(
-                    "window.onChangeProcessStatus({})",
-                    serde_json::to_string(&process_status).unwrap()
-                );
-                println!("<-- {}", eval);
-                handle
-                    .dispatch(move |web_view| web_view.eval(&eval))
-                    .unwrap_or_else(|err| eprintln!("{}", err));
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            match lines.next_line().await {
+                Ok(Some(line)) if line == *token => {}
+                // A starting instance checks that the port is open.
+                Ok(None) => return,
+                _ => {
+                    warn!("Instance socket: rejected a connection without the token");
+                    return;
+                }
+            }
+            while let Ok(Some(line)) = lines.next_line().await {
+                debug!("==> {}", line);
+                let response = match Command::parse(&line) {
+                    Err(err) => Response::Err(err),
+                    Ok(Command::Watch {}) => {
+                        let rx = app_mutex.lock().await.subscribe();
+                        stream_events(rx, &mut writer).await;
+                        break;
+                    }
+                    Ok(command) if !command.is_allowed_on_instance_socket() => Response::Err(
+                        ApplyError::InvalidCommand("not available on the instance socket".into()),
+                    ),
+                    Ok(command) => execute(&*app_mutex.lock().await, command).await,
+                };
+                let reply = format!("{}\n", serde_json::to_string(&response).unwrap());
+                if writer.write_all(reply.as_bytes()).await.is_err() {
+                    break;
+                }
             }
         });
-        Self {
-            app: Arc::new(Mutex::new(App::new(process_status_sender))),
+    }
+}
+
+pub struct AppWrapper {
+    app: Arc<Mutex<App>>,
+}
+
+impl AppWrapper {
+    pub fn new(
+        handle: Handle<()>,
+        instance_listener: Option<(std::net::TcpListener, String)>,
+        debug: bool,
+    ) -> Self {
+        let app = App::new(debug);
+        forward_to_window(handle, app.subscribe());
+        let app = Arc::new(Mutex::new(app));
+        if let Some((listener, token)) = instance_listener {
+            spawn(serve_instance(listener, token, app.clone()));
         }
+        Self { app }
     }
 
     pub fn invoke_handler(&mut self, web_view: &mut WebView<()>, arg: &str) -> WVResult {
@@ -86,7 +154,7 @@ impl AppWrapper {
         let arg = arg.to_owned();
         let app = self.app.clone();
         spawn(async move {
-            println!("--> {}", arg);
+            debug!("--> {}", arg);
             invoke_handler(handle, &arg, &app).await;
         });
         Ok(())